@@ -95,7 +95,51 @@ fn compare_routers(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, compare_routers);
+fn params(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parameters");
+
+    let paths = [
+        "/users/978",
+        "/repos/rust-lang/rust/issues/12345/comments",
+        "/files/report.pdf",
+    ];
+
+    let mut matchit = matchit::Router::new();
+    for route in [
+        "/users/:id",
+        "/repos/:owner/:repo/issues/:number/comments",
+        "/files/:name",
+    ] {
+        matchit.insert(route, true).unwrap();
+    }
+    group.bench_function("segments", |b| {
+        b.iter(|| {
+            for path in black_box(paths) {
+                black_box(matchit.at(path).unwrap());
+            }
+        });
+    });
+
+    let mut matchit = matchit::Router::new();
+    for route in [
+        "/users/:id",
+        "/repos/:owner/:repo/issues/:number/comments",
+        "/files/:name.:ext",
+    ] {
+        matchit.insert(route, true).unwrap();
+    }
+    group.bench_function("suffixes", |b| {
+        b.iter(|| {
+            for path in black_box(paths) {
+                black_box(matchit.at(path).unwrap());
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, compare_routers, params);
 criterion_main!(benches);
 
 macro_rules! register {
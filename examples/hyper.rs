@@ -1,10 +1,11 @@
-use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::{Arc, Mutex};
 
+use hyper::header::ALLOW;
 use hyper::server::Server;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response};
+use matchit::{MethodError, MethodRouter};
 use tower::util::BoxCloneService;
 use tower::Service as _;
 
@@ -29,25 +30,34 @@ async fn not_found(_req: Request<Body>) -> hyper::Result<Response<Body>> {
 // require the service to implement `Sync`.
 type Service = Mutex<BoxCloneService<Request<Body>, Response<Body>, hyper::Error>>;
 
-// We use a `HashMap` to hold a `Router` for each HTTP method. This allows us
-// to register the same route for multiple methods.
-type Router = HashMap<Method, matchit::Router<Service>>;
+// A `MethodRouter` holds a service for each HTTP method of a route. This allows
+// us to register the same route for multiple methods.
+type Router = MethodRouter<Service>;
 
 async fn route(router: Arc<Router>, req: Request<Body>) -> hyper::Result<Response<Body>> {
-    // find the subrouter for this request method
-    let router = match router.get(req.method()) {
-        Some(router) => router,
-        // if there are no routes for this method, respond with 405 Method Not Allowed
-        None => return Ok(Response::builder().status(405).body(Body::empty()).unwrap()),
-    };
-
-    // find the service for this request path
-    match router.at(req.uri().path()) {
+    // find the service for this request method and path
+    match router.at(req.method(), req.uri().path()) {
         Ok(found) => {
             // lock the service for a very short time, just to clone the service
             let mut service = found.value.lock().unwrap().clone();
             service.call(req).await
         }
+        // the path exists, but not for this method
+        Err(MethodError::MethodNotAllowed { allowed }) => {
+            // answer `OPTIONS` requests with the allowed methods,
+            // otherwise respond with 405 Method Not Allowed
+            let status = if req.method() == Method::OPTIONS {
+                204
+            } else {
+                405
+            };
+
+            Ok(Response::builder()
+                .status(status)
+                .header(ALLOW, allowed.join(", "))
+                .body(Body::empty())
+                .unwrap())
+        }
         // if we there is no matching service, call the 404 handler
         Err(_) => not_found(req).await,
     }
@@ -60,16 +70,20 @@ async fn main() {
 
     // GET / => `index`
     router
-        .entry(Method::GET)
-        .or_default()
-        .insert("/", BoxCloneService::new(service_fn(index)).into())
+        .insert(
+            Method::GET.as_str(),
+            "/",
+            BoxCloneService::new(service_fn(index)).into(),
+        )
         .unwrap();
 
     // GET /blog => `blog`
     router
-        .entry(Method::GET)
-        .or_default()
-        .insert("/blog", BoxCloneService::new(service_fn(blog)).into())
+        .insert(
+            Method::GET.as_str(),
+            "/blog",
+            BoxCloneService::new(service_fn(blog)).into(),
+        )
         .unwrap();
 
     // boilerplate for the hyper service
@@ -0,0 +1,62 @@
+use hyper::server::Server;
+use hyper::{Body, Request, Response};
+use matchit::{MatchError, OwnedParams, Router, RouterService};
+use tower::make::Shared;
+use tower::service_fn;
+use tower::util::BoxCloneService;
+
+// GET /
+async fn index(_req: Request<Body>) -> hyper::Result<Response<Body>> {
+    Ok(Response::new(Body::from("Hello, world!")))
+}
+
+// GET /blog/:post
+async fn blog(req: Request<Body>) -> hyper::Result<Response<Body>> {
+    // the router inserts the parameters of the route into the request extensions
+    let params = req.extensions().get::<OwnedParams>().unwrap();
+    let post = params.get("post").unwrap();
+    Ok(Response::new(Body::from(format!("Reading post {}", post))))
+}
+
+// 404 handler
+async fn not_found(req: Request<Body>) -> hyper::Result<Response<Body>> {
+    // redirect paths with a missing or extra trailing slash
+    let err = req.extensions().get::<MatchError>().unwrap();
+    if let Some(location) = err.redirect_path(req.uri().path()) {
+        return Ok(Response::builder()
+            .status(308)
+            .header("Location", location)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    Ok(Response::builder().status(404).body(Body::empty()).unwrap())
+}
+
+// We can use `BoxCloneService` to erase the type of each handler service.
+type Service = BoxCloneService<Request<Body>, Response<Body>, hyper::Error>;
+
+#[tokio::main]
+async fn main() {
+    // Create a router and register our routes.
+    let mut router = Router::<Service>::new();
+
+    // GET / => `index`
+    router
+        .insert("/", BoxCloneService::new(service_fn(index)))
+        .unwrap();
+
+    // GET /blog/:post => `blog`
+    router
+        .insert("/blog/:post", BoxCloneService::new(service_fn(blog)))
+        .unwrap();
+
+    // the router is a service itself, requests that don't match a route go to `not_found`
+    let service = RouterService::new(router).fallback(BoxCloneService::new(service_fn(not_found)));
+
+    // run the server, cloning the service for every connection
+    Server::bind(&([127, 0, 0, 1], 3000).into())
+        .serve(Shared::new(service))
+        .await
+        .unwrap()
+}
@@ -2,7 +2,7 @@
 use libfuzzer_sys::fuzz_target;
 
 fuzz_target!(|data: (Vec<(String, i32)>, String, Option<bool>)| {
-    let mut matcher = matchit::Node::new();
+    let mut matcher = matchit::Router::new();
 
     for (key, item) in data.0 {
         if matcher.insert(key, item).is_err() {
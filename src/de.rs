@@ -0,0 +1,434 @@
+use crate::{ParamError, Params, ParamsIter};
+
+use serde::de::value::{BorrowedStrDeserializer, StrDeserializer};
+use serde::de::{self, DeserializeSeed, Visitor};
+use std::fmt::Display;
+
+impl de::Error for ParamError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ParamError::Custom {
+            message: msg.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ParamError::Missing {
+            key: field.to_owned(),
+        }
+    }
+}
+
+// Deserializes a list of parameters as a map, a sequence, or a single value.
+pub(crate) struct ParamsDeserializer<'p, 'k, 'v> {
+    params: &'p Params<'k, 'v>,
+}
+
+impl<'p, 'k, 'v> ParamsDeserializer<'p, 'k, 'v> {
+    pub(crate) fn new(params: &'p Params<'k, 'v>) -> Self {
+        Self { params }
+    }
+
+    // primitive types can only be deserialized from a route with a single parameter
+    fn single(&self) -> Result<ValueDeserializer<'k, 'v>, ParamError> {
+        let mut iter = self.params.iter();
+
+        match (iter.next(), iter.next()) {
+            (Some((key, value)), None) => Ok(ValueDeserializer { key, value }),
+            _ => Err(ParamError::Custom {
+                message: format!("expected 1 parameter, found {}", self.params.len()),
+            }),
+        }
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), ParamError> {
+        if self.params.len() != len {
+            return Err(ParamError::Custom {
+                message: format!("expected {} parameters, found {}", len, self.params.len()),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'v>,
+        {
+            let value = self.single()?;
+            let key = value.key;
+            value.$method(visitor).map_err(|err| err.with_key(key))
+        }
+    )*};
+}
+
+impl<'p, 'k, 'v> de::Deserializer<'v> for ParamsDeserializer<'p, 'k, 'v> {
+    type Error = ParamError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_map(MapAccess {
+            iter: self.params.iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_seq(SeqAccess {
+            iter: self.params.iter(),
+        })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        self.check_len(len)?;
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        let value = self.single()?;
+        let key = value.key;
+        value
+            .deserialize_enum(name, variants, visitor)
+            .map_err(|err| err.with_key(key))
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_single! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_identifier
+    }
+}
+
+struct MapAccess<'p, 'k, 'v> {
+    iter: ParamsIter<'p, 'k, 'v>,
+    value: Option<(&'k str, &'v str)>,
+}
+
+impl<'p, 'k, 'v> de::MapAccess<'v> for MapAccess<'p, 'k, 'v> {
+    type Error = ParamError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'v>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(StrDeserializer::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'v>,
+    {
+        let (key, value) = self.value.take().ok_or_else(|| ParamError::Custom {
+            message: "value requested before key".to_owned(),
+        })?;
+
+        seed.deserialize(ValueDeserializer { key, value })
+            .map_err(|err| err.with_key(key))
+    }
+}
+
+struct SeqAccess<'p, 'k, 'v> {
+    iter: ParamsIter<'p, 'k, 'v>,
+}
+
+impl<'p, 'k, 'v> de::SeqAccess<'v> for SeqAccess<'p, 'k, 'v> {
+    type Error = ParamError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'v>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => seed
+                .deserialize(ValueDeserializer { key, value })
+                .map(Some)
+                .map_err(|err| err.with_key(key)),
+            None => Ok(None),
+        }
+    }
+}
+
+// Deserializes the value of a single parameter.
+struct ValueDeserializer<'k, 'v> {
+    key: &'k str,
+    value: &'v str,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident)*) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'v>,
+        {
+            match self.value.parse() {
+                Ok(value) => visitor.$visit(value),
+                Err(err) => Err(ParamError::Invalid {
+                    key: self.key.to_owned(),
+                    message: err.to_string(),
+                }),
+            }
+        }
+    )*};
+}
+
+impl<'k, 'v> de::Deserializer<'v> for ValueDeserializer<'k, 'v> {
+    type Error = ParamError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_borrowed_bytes(self.value.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'v>,
+    {
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.value))
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool
+        deserialize_i8 => visit_i8
+        deserialize_i16 => visit_i16
+        deserialize_i32 => visit_i32
+        deserialize_i64 => visit_i64
+        deserialize_i128 => visit_i128
+        deserialize_u8 => visit_u8
+        deserialize_u16 => visit_u16
+        deserialize_u32 => visit_u32
+        deserialize_u64 => visit_u64
+        deserialize_u128 => visit_u128
+        deserialize_f32 => visit_f32
+        deserialize_f64 => visit_f64
+        deserialize_char => visit_char
+    }
+
+    serde::forward_to_deserialize_any! {
+        <W: Visitor<'v>>
+        str string unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    fn params<'a>(pairs: &[(&'a str, &'a str)]) -> Params<'a, 'a> {
+        let mut params = Params::new();
+        for (key, value) in pairs {
+            params.push(key.as_bytes(), value.as_bytes());
+        }
+        params
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Issue<'a> {
+            repo: &'a str,
+            id: u32,
+            draft: Option<bool>,
+        }
+
+        let params = params(&[("repo", "matchit"), ("id", "42")]);
+        assert_eq!(
+            params.deserialize::<Issue<'_>>(),
+            Ok(Issue {
+                repo: "matchit",
+                id: 42,
+                draft: None
+            })
+        );
+
+        let params = self::params(&[("repo", "matchit"), ("id", "x")]);
+        assert!(matches!(
+            params.deserialize::<Issue<'_>>(),
+            Err(ParamError::Invalid { key, .. }) if key == "id"
+        ));
+
+        let params = self::params(&[("repo", "matchit")]);
+        assert_eq!(
+            params.deserialize::<Issue<'_>>(),
+            Err(ParamError::Missing { key: "id".into() })
+        );
+    }
+
+    #[test]
+    fn deserialize_seq() {
+        let params = params(&[("a", "1"), ("b", "two"), ("c", "3.5")]);
+        assert_eq!(params.deserialize(), Ok((1_u8, "two", 3.5_f32)));
+        assert_eq!(
+            params.deserialize(),
+            Ok(vec!["1".to_owned(), "two".to_owned(), "3.5".to_owned()])
+        );
+        assert!(matches!(
+            params.deserialize::<(u8, u8, f32)>(),
+            Err(ParamError::Invalid { key, .. }) if key == "b"
+        ));
+        assert!(matches!(
+            params.deserialize::<(u8, String)>(),
+            Err(ParamError::Custom { .. })
+        ));
+    }
+
+    #[test]
+    fn deserialize_single() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Kind {
+            Issue,
+            Pull,
+        }
+
+        assert_eq!(params(&[("id", "7")]).deserialize(), Ok(7_u64));
+        assert_eq!(params(&[("kind", "pull")]).deserialize(), Ok(Kind::Pull));
+        assert!(matches!(
+            params(&[("kind", "commit")]).deserialize::<Kind>(),
+            Err(ParamError::Invalid { key, .. }) if key == "kind"
+        ));
+        assert!(matches!(
+            params(&[("a", "1"), ("b", "2")]).deserialize::<u64>(),
+            Err(ParamError::Custom { .. })
+        ));
+
+        let map: HashMap<String, String> = params(&[("a", "1"), ("b", "2")]).deserialize().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["b"], "2");
+    }
+}
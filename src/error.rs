@@ -1,6 +1,7 @@
-use crate::tree::{denormalize_params, Node};
+use crate::tree::{denormalize_params, find_wildcard, Node, NodeType, ParamRemapping};
 
 use std::fmt;
+use std::ops::Deref;
 
 /// Represents errors that can occur when inserting a new route.
 #[non_exhaustive]
@@ -8,8 +9,14 @@ use std::fmt;
 pub enum InsertError {
     /// Attempted to insert a path that conflicts with an existing route.
     Conflict {
+        /// The route that was being inserted.
+        route: String,
         /// The existing route that the insertion is conflicting with.
         with: String,
+        /// The segment of the inserted route where the two routes collide.
+        segment: String,
+        /// Why the two routes cannot coexist.
+        kind: ConflictKind,
     },
     /// Only one parameter per route segment is allowed.
     TooManyParams,
@@ -17,17 +24,54 @@ pub enum InsertError {
     UnnamedParam,
     /// Catch-all parameters are only allowed at the end of a path.
     InvalidCatchAll,
+    /// Attempted to insert a route under a name that is already in use.
+    DuplicateName {
+        /// The name of the route.
+        name: String,
+    },
+    /// Parameter constraints must be a name enclosed in angle brackets, ex: `/:id<num>`.
+    InvalidConstraint,
+    /// Only trailing parameters that take up an entire segment can be optional, ex: `/posts/:page?`.
+    InvalidOptional,
+    /// A parameter referenced a constraint that was not registered with
+    /// [`Router::constraint`](crate::Router::constraint).
+    UnknownConstraint {
+        /// The name of the constraint.
+        name: String,
+    },
 }
 
 impl fmt::Display for InsertError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Conflict { with } => {
+            Self::Conflict {
+                route,
+                with,
+                segment,
+                kind,
+            } => {
                 write!(
                     f,
-                    "insertion failed due to conflict with previously registered route: {}",
-                    with
-                )
+                    "insertion of '{}' failed due to conflict with previously registered route '{}': ",
+                    route, with
+                )?;
+
+                match kind {
+                    ConflictKind::Duplicate => write!(f, "both routes match the same paths"),
+                    ConflictKind::ParamName => {
+                        write!(f, "the parameter in '{}' is named differently", segment)
+                    }
+                    ConflictKind::CatchAll => write!(
+                        f,
+                        "a named parameter and a catch-all parameter collide at '{}'",
+                        segment
+                    ),
+                    ConflictKind::Constraint => write!(
+                        f,
+                        "the parameter in '{}' is constrained differently",
+                        segment
+                    ),
+                }
             }
             Self::TooManyParams => write!(f, "only one parameter is allowed per path segment"),
             Self::UnnamedParam => write!(f, "parameters must be registered with a name"),
@@ -35,6 +79,20 @@ impl fmt::Display for InsertError {
                 f,
                 "catch-all parameters are only allowed at the end of a route"
             ),
+            Self::DuplicateName { name } => {
+                write!(f, "a route named '{}' is already registered", name)
+            }
+            Self::InvalidConstraint => write!(
+                f,
+                "parameter constraints must be a name enclosed in angle brackets"
+            ),
+            Self::InvalidOptional => write!(
+                f,
+                "only trailing parameters that take up an entire segment can be optional"
+            ),
+            Self::UnknownConstraint { name } => {
+                write!(f, "no constraint named '{}' is registered", name)
+            }
         }
     }
 }
@@ -42,41 +100,317 @@ impl fmt::Display for InsertError {
 impl std::error::Error for InsertError {}
 
 impl InsertError {
-    pub(crate) fn conflict<T>(route: &[u8], prefix: &[u8], current: &Node<T>) -> Self {
-        // The new route would have had to replace the current node in the tree.
-        if prefix == current.prefix {
-            let mut route = route.to_owned();
-            denormalize_params(&mut route, &current.param_remapping);
-            return InsertError::Conflict {
-                with: String::from_utf8(route).unwrap(),
-            };
+    pub(crate) fn conflict<T>(
+        route: &[u8],
+        prefix: &[u8],
+        current: &Node<T>,
+        params: &ParamRemapping,
+        route_of: &dyn Fn(&T) -> String,
+    ) -> Self {
+        // the new route collides with the current node
+        let collision = route.len() - prefix.len();
+
+        // the existing route passing through the current node, as it was inserted
+        let with = route_of(current.closest_value());
+
+        let mut new = route.to_owned();
+        denormalize_params(&mut new, params);
+
+        let mut at = route[..collision].to_owned();
+        denormalize_params(&mut at, params);
+        let mut at = at.len();
+
+        let kind = if prefix == current.prefix {
+            // the routes have the same shape
+            match params
+                .iter()
+                .zip(&current.param_remapping)
+                .position(|(a, b)| a != b)
+            {
+                Some(i) => {
+                    at = nth_param(&new, i).unwrap_or(at);
+                    ConflictKind::ParamName
+                }
+                None => {
+                    at = last_segment(&new);
+                    ConflictKind::Duplicate
+                }
+            }
+        } else if prefix[0] != current.prefix[0] {
+            // both are wildcards, but of a different kind
+            ConflictKind::CatchAll
+        } else if current.node_type == NodeType::CatchAll {
+            ConflictKind::ParamName
+        } else {
+            ConflictKind::Constraint
+        };
+
+        InsertError::Conflict {
+            segment: segment(&new, at).to_owned(),
+            route: String::from_utf8(new).unwrap(),
+            with,
+            kind,
         }
+    }
 
-        let mut route = route[..route.len() - prefix.len()].to_owned();
+    // a route that was inserted twice
+    pub(crate) fn duplicate(route: String) -> Self {
+        InsertError::Conflict {
+            segment: segment(route.as_bytes(), last_segment(route.as_bytes())).to_owned(),
+            with: route.clone(),
+            route,
+            kind: ConflictKind::Duplicate,
+        }
+    }
+}
+
+/// The reason two routes conflict, see [`InsertError::Conflict`].
+///
+/// Static segments can overlap with parameters of either kind, so they never conflict
+/// unless the routes are identical. In particular, a catch-all and a static segment at the
+/// same position, like `/files/*path` and `/files/readme`, are not a conflict: the static
+/// route takes priority, and the catch-all matches every other path.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ConflictKind {
+    /// The routes match the same paths, ex: `/users/:id` and `/users/:id`.
+    Duplicate,
+    /// The routes only differ in the names of their parameters, ex: `/users/:id`
+    /// and `/users/:user_id`.
+    ParamName,
+    /// A named parameter and a catch-all parameter at the same position, ex: `/files/:name`
+    /// and `/files/*path`.
+    CatchAll,
+    /// Named parameters with different constraints at the same position, ex: `/users/:id<num>`
+    /// and `/users/:id<slug>`. Only one parameter can take up a position, see
+    /// [`Router::constraint`](crate::Router::constraint).
+    Constraint,
+}
 
-        if !route.ends_with(&current.prefix) {
-            route.extend_from_slice(&current.prefix);
+// Returns the position of the nth named parameter in a route.
+fn nth_param(route: &[u8], n: usize) -> Option<usize> {
+    let mut offset = 0;
+    let mut n = n;
+
+    while let Some((wildcard, i)) = find_wildcard(&route[offset..]).ok()? {
+        if wildcard[0] == b':' {
+            if n == 0 {
+                return Some(offset + i);
+            }
+            n -= 1;
         }
+        offset += i + wildcard.len();
+    }
+
+    None
+}
+
+// Returns the segment of a route containing the given position.
+fn segment(route: &[u8], at: usize) -> &str {
+    let start = route[..at]
+        .iter()
+        .rposition(|&c| c == b'/')
+        .map_or(0, |i| i + 1);
+    let end = route[at..]
+        .iter()
+        .position(|&c| c == b'/')
+        .map_or(route.len(), |i| at + i);
+
+    std::str::from_utf8(&route[start..end]).unwrap()
+}
 
-        let mut last = current;
-        while let Some(node) = last.children.first() {
-            last = node;
+// Returns the position of the last segment of a route, ignoring a trailing slash.
+fn last_segment(route: &[u8]) -> usize {
+    match route.strip_suffix(b"/") {
+        Some(rest) if !rest.is_empty() => rest.len(),
+        _ => route.len(),
+    }
+}
+
+/// A failed attempt to merge two routers, returned by [`Router::merge`](crate::Router::merge).
+///
+/// Contains every route that could not be merged, along with the error it caused.
+/// All other routes were merged successfully.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeError(pub(crate) Vec<(String, InsertError)>);
+
+impl MergeError {
+    /// Returns a list of the routes that failed to merge and their errors.
+    pub fn into_errors(self) -> Vec<(String, InsertError)> {
+        self.0
+    }
+}
+
+impl Deref for MergeError {
+    type Target = [(String, InsertError)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to merge {} route(s):", self.0.len())?;
+
+        for (route, err) in &self.0 {
+            write!(f, "\n{}: {}", route, err)?;
         }
 
-        let mut current = current.children.first();
-        while let Some(node) = current {
-            route.extend_from_slice(&node.prefix);
-            current = node.children.first();
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Represents errors that can occur when building a URL with [`Router::url_for`](crate::Router::url_for).
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum UrlError {
+    /// No route was registered under the given name.
+    UnknownRoute,
+    /// A parameter of the route was not provided.
+    MissingParam {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// A parameter was provided that the route does not contain.
+    ExtraParam {
+        /// The name of the parameter.
+        name: String,
+    },
+    /// The value of a parameter is empty, or the value of a named parameter contains a `/`.
+    InvalidParam {
+        /// The name of the parameter.
+        name: String,
+    },
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownRoute => write!(f, "no route registered under the given name"),
+            Self::MissingParam { name } => write!(f, "missing value for parameter '{}'", name),
+            Self::ExtraParam { name } => write!(f, "route has no parameter named '{}'", name),
+            Self::InvalidParam { name } => {
+                write!(f, "invalid value for parameter '{}'", name)
+            }
         }
+    }
+}
 
-        denormalize_params(&mut route, &last.param_remapping);
+impl std::error::Error for UrlError {}
 
-        InsertError::Conflict {
-            with: String::from_utf8(route).unwrap(),
+/// An error decoding the percent-escapes in a parameter value, returned by
+/// [`Params::get_decoded`](crate::Params::get_decoded).
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DecodeError {
+    /// A `%` was not followed by two hexadecimal digits.
+    InvalidEscape,
+    /// The decoded value is not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            DecodeError::InvalidEscape => "decode error: invalid percent-escape",
+            DecodeError::InvalidUtf8 => "decode error: decoded value is not valid UTF-8",
+        };
+
+        write!(f, "{}", msg)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// An error creating a [`FrozenRouter`](crate::FrozenRouter) with
+/// [`Router::freeze`](crate::Router::freeze), or loading one with
+/// [`FrozenRouter::from_bytes`](crate::FrozenRouter::from_bytes).
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FrozenError {
+    /// The router is too large to be frozen, its routes take up more than 4 GiB.
+    TooLarge,
+    /// The bytes don't start with the header of a frozen router.
+    InvalidHeader,
+    /// The bytes were frozen by an incompatible version of this crate.
+    UnsupportedVersion {
+        /// The version of the format the bytes were written in.
+        version: u32,
+    },
+    /// The bytes are truncated or corrupted.
+    Corrupted,
+}
+
+impl fmt::Display for FrozenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrozenError::TooLarge => write!(f, "frozen router error: router is too large"),
+            FrozenError::InvalidHeader => write!(f, "frozen router error: invalid header"),
+            FrozenError::UnsupportedVersion { version } => {
+                write!(f, "frozen router error: unsupported version {}", version)
+            }
+            FrozenError::Corrupted => write!(f, "frozen router error: truncated or corrupted"),
+        }
+    }
+}
+
+impl std::error::Error for FrozenError {}
+
+/// An error extracting a typed value from route parameters, returned by
+/// [`Params::parse`](crate::Params::parse).
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ParamError {
+    /// No parameter was registered under the given key.
+    Missing {
+        /// The key of the parameter.
+        key: String,
+    },
+    /// The value of the parameter could not be parsed.
+    Invalid {
+        /// The key of the parameter.
+        key: String,
+        /// The reason the value was rejected.
+        message: String,
+    },
+    /// Any other error, such as the number of parameters not matching the target type.
+    Custom {
+        /// The error message.
+        message: String,
+    },
+}
+
+impl ParamError {
+    // attributes an error to the given parameter
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_key(self, key: &str) -> Self {
+        match self {
+            ParamError::Custom { message } => ParamError::Invalid {
+                key: key.to_owned(),
+                message,
+            },
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { key } => write!(f, "missing parameter '{}'", key),
+            Self::Invalid { key, message } => {
+                write!(f, "invalid value for parameter '{}': {}", key, message)
+            }
+            Self::Custom { message } => write!(f, "{}", message),
         }
     }
 }
 
+impl std::error::Error for ParamError {}
+
 /// A failed match attempt.
 ///
 /// ```
@@ -113,6 +447,31 @@ pub enum MatchError {
 }
 
 impl MatchError {
+    /// Returns the path a client should be redirected to, if this error was caused
+    /// by a missing or extra trailing slash.
+    ///
+    /// ```
+    /// use matchit::{MatchError, Router};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    ///
+    /// let err = router.at("/home/").unwrap_err();
+    /// assert_eq!(err.redirect_path("/home/").as_deref(), Some("/home"));
+    ///
+    /// let err = router.at("/foobar").unwrap_err();
+    /// assert_eq!(err.redirect_path("/foobar"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn redirect_path(&self, path: &str) -> Option<String> {
+        match self {
+            MatchError::MissingTrailingSlash => Some(format!("{}/", path)),
+            MatchError::ExtraTrailingSlash => path.strip_suffix('/').map(str::to_owned),
+            MatchError::NotFound => None,
+        }
+    }
+
     pub(crate) fn unsure(full_path: &[u8]) -> Self {
         if full_path[full_path.len() - 1] == b'/' {
             MatchError::ExtraTrailingSlash
@@ -135,3 +494,56 @@ impl fmt::Display for MatchError {
 }
 
 impl std::error::Error for MatchError {}
+
+/// A failed match attempt with [`MethodRouter::at`](crate::MethodRouter::at).
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MethodError {
+    /// The path did not match any route.
+    Match(MatchError),
+    /// The path matched routes, but none of them were registered for the method.
+    MethodNotAllowed {
+        /// The methods the matching routes were registered for.
+        allowed: Vec<String>,
+    },
+}
+
+impl MethodError {
+    // the error for a path that matched routes of the `allowed` methods only, if any
+    pub(crate) fn not_allowed(err: MatchError, allowed: Vec<String>) -> Self {
+        if allowed.is_empty() {
+            return MethodError::Match(err);
+        }
+
+        MethodError::MethodNotAllowed { allowed }
+    }
+
+    /// Returns the methods allowed for the path, if it matched a route.
+    pub fn allowed(&self) -> Option<&[String]> {
+        match self {
+            MethodError::MethodNotAllowed { allowed } => Some(allowed),
+            MethodError::Match(_) => None,
+        }
+    }
+}
+
+impl From<MatchError> for MethodError {
+    fn from(err: MatchError) -> Self {
+        MethodError::Match(err)
+    }
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Match(err) => write!(f, "{}", err),
+            Self::MethodNotAllowed { allowed } => write!(
+                f,
+                "match error: method not allowed, expected one of: {}",
+                allowed.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MethodError {}
@@ -0,0 +1,688 @@
+use crate::router::fill_optional;
+use crate::tree::{match_path, split_constraint, Constraint, Node, NodeRef, NodeType};
+use crate::{FrozenError, Match, MatchError, Query};
+
+use std::convert::TryFrom;
+use std::str;
+
+// The layout of a frozen router, in which every integer is a little-endian `u32`:
+//
+// - the header: `MAGIC`, `VERSION`, and the number of elements in each of the sections below
+// - nodes: `NODE_WORDS` words for each node, in pre-order, so the root comes first and the
+//   children of a node always come after it
+// - children: the index of each child node, the children of a node are contiguous
+// - remappings: the original key of each route parameter, as a range of the data section
+// - routes: each route, as a range of the data section, in the order of `Router::iter`
+// - constraints: the name of each distinct constraint, as a range of the data section
+// - data: the prefixes and indices of the nodes, and the strings of the tables above
+const MAGIC: &[u8; 8] = b"matchit\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 4 * 7;
+
+// the words of a node record
+const PREFIX: usize = 0;
+const INDICES: usize = 2;
+const CHILDREN: usize = 4;
+const REMAPPING: usize = 6;
+const FLAGS: usize = 8;
+const VALUE: usize = 9;
+const CONSTRAINT: usize = 10;
+const NODE_WORDS: usize = 11;
+
+// the bits of the flags word, the lowest two hold the node type
+const TYPE_MASK: u32 = 0b11;
+const WILD_CHILD: u32 = 1 << 2;
+const HAS_VALUE: u32 = 1 << 3;
+
+// the absence of a value or constraint
+const NONE: u32 = u32::MAX;
+
+/// An immutable router stored in a flat, contiguous buffer, created by [`Router::freeze`](crate::Router::freeze).
+///
+/// The buffer can be written out with [`as_bytes`](FrozenRouter::as_bytes) and loaded back with
+/// [`from_bytes`](FrozenRouter::from_bytes), which checks it but doesn't rebuild the tree, so
+/// any `B: AsRef<[u8]>` works, such as a `Vec<u8>`, a `&[u8]`, or a memory-mapped file. Lookups
+/// read the buffer in place and return the same results as [`Router::at`](crate::Router::at).
+///
+/// Values can't be stored in bytes, so a match holds the index of the route in
+/// [`Router::iter`](crate::Router::iter) instead, which can be used to look up the value in a
+/// separate table.
+///
+/// ```rust
+/// use matchit::{FrozenRouter, Router};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut router = Router::new();
+/// router.insert("/home", "Welcome!")?;
+/// router.insert("/users/:id", "A User")?;
+///
+/// let bytes = router.freeze()?.as_bytes().to_vec();
+/// let values = router.into_iter().map(|(_, value)| value).collect::<Vec<_>>();
+///
+/// let frozen = FrozenRouter::from_bytes(&bytes[..])?;
+/// let matched = frozen.at("/users/978")?;
+/// assert_eq!(matched.params.get("id"), Some("978"));
+/// assert_eq!(values[matched.value], "A User");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct FrozenRouter<B = Vec<u8>> {
+    bytes: B,
+    sections: Sections,
+    // the registered constraint for each entry of the constraints section
+    constraints: Vec<Option<Constraint>>,
+}
+
+impl FrozenRouter {
+    // Flattens a tree whose values are indices into `routes`.
+    pub(crate) fn new<'r>(
+        root: &Node<usize>,
+        routes: impl Iterator<Item = &'r str>,
+    ) -> Result<Self, FrozenError> {
+        let mut writer = Writer::default();
+        writer.node(root);
+
+        let routes = routes
+            .map(|route| writer.data(route.as_bytes()))
+            .collect::<Vec<_>>();
+        let names = writer
+            .constraints
+            .iter()
+            .map(|&(name, _)| name)
+            .collect::<Vec<_>>();
+        let names = names
+            .into_iter()
+            .map(|name| writer.data(name))
+            .collect::<Vec<_>>();
+
+        let counts = [
+            writer.nodes.len() / NODE_WORDS,
+            writer.children.len(),
+            writer.remappings.len() / 2,
+            routes.len(),
+            names.len(),
+            writer.data.len(),
+        ];
+
+        let mut bytes = Vec::with_capacity(
+            HEADER_LEN
+                + 4 * (writer.nodes.len() + writer.children.len() + writer.remappings.len())
+                + 8 * (routes.len() + names.len())
+                + writer.data.len(),
+        );
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+        let words = counts
+            .iter()
+            .copied()
+            .chain(writer.nodes.iter().copied())
+            .chain(writer.children.iter().copied())
+            .chain(writer.remappings.iter().copied())
+            .chain(routes.iter().flatten().copied())
+            .chain(names.iter().flatten().copied());
+
+        for word in words {
+            let word = u32::try_from(word).map_err(|_| FrozenError::TooLarge)?;
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&writer.data);
+
+        let mut frozen = Self::from_bytes(bytes)?;
+        for (slot, (_, constraint)) in frozen.constraints.iter_mut().zip(writer.constraints) {
+            *slot = Some(constraint.clone());
+        }
+
+        Ok(frozen)
+    }
+}
+
+impl<B: AsRef<[u8]>> FrozenRouter<B> {
+    /// Loads a router from the bytes returned by [`as_bytes`](FrozenRouter::as_bytes).
+    ///
+    /// The bytes are checked in a single pass, so that lookups can't read out of bounds or
+    /// loop forever, but they are not copied. The bytes must come from the same version of
+    /// this crate.
+    ///
+    /// Constraints can't be stored in bytes, so they must be registered again with
+    /// [`constraint`](FrozenRouter::constraint). Until then, a constrained parameter
+    /// rejects every value.
+    pub fn from_bytes(bytes: B) -> Result<Self, FrozenError> {
+        let sections = Sections::parse(bytes.as_ref())?;
+
+        let tables = Tables {
+            bytes: bytes.as_ref(),
+            sections,
+            constraints: &[],
+        };
+        tables.validate()?;
+
+        Ok(Self {
+            constraints: vec![None; sections.constraints.len],
+            bytes,
+            sections,
+        })
+    }
+
+    /// Returns the bytes of the router, which can be loaded back with
+    /// [`from_bytes`](FrozenRouter::from_bytes).
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    /// Returns the underlying buffer of the router.
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+
+    /// Register a named constraint for route parameters.
+    ///
+    /// See [`Router::constraint`](crate::Router::constraint) for details. Unlike a [`Router`](crate::Router),
+    /// the routes of a frozen router are already in place, so this replaces the constraint
+    /// of every parameter that uses it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::{FrozenRouter, Router};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    /// router.insert("/users/:id<num>", "A User")?;
+    ///
+    /// let mut frozen = FrozenRouter::from_bytes(router.freeze()?.into_bytes())?;
+    /// assert!(frozen.at("/users/978").is_err());
+    ///
+    /// frozen.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    /// assert_eq!(frozen.at("/users/978")?.value, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn constraint(
+        &mut self,
+        name: impl AsRef<str>,
+        constraint: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        let constraint = Constraint::new(constraint);
+        let tables = Tables {
+            bytes: self.bytes.as_ref(),
+            sections: self.sections,
+            constraints: &[],
+        };
+
+        for (i, slot) in self.constraints.iter_mut().enumerate() {
+            if tables.string(tables.sections.constraints, i) == name.as_ref() {
+                *slot = Some(constraint.clone());
+            }
+        }
+    }
+
+    /// Tries to find the route matching the given path, returning the index of the route in
+    /// [`Router::iter`](crate::Router::iter) as the value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    /// router.insert("/users/:id", "A User")?;
+    ///
+    /// let frozen = router.freeze()?;
+    /// let matched = frozen.at("/users/978")?;
+    /// assert_eq!(matched.value, 1);
+    /// assert_eq!(matched.route(), "/users/:id");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at<'m, 'p>(&'m self, path: &'p str) -> Result<Match<'m, 'p, usize>, MatchError> {
+        let tables = self.tables();
+        let root = FrozenNode {
+            tables: &tables,
+            index: 0,
+        };
+
+        let (node, mut params) = match_path(root, path.as_bytes(), |_| true)?;
+        let value = node.word(VALUE);
+        let route = tables.string(tables.sections.routes, value);
+
+        fill_optional(route, &mut params);
+        Ok(Match {
+            value,
+            params,
+            query: Query::default(),
+            route,
+        })
+    }
+
+    /// Returns an iterator over the routes in the router, in the order of
+    /// [`Router::iter`](crate::Router::iter).
+    pub fn routes(&self) -> impl Iterator<Item = &str> {
+        let tables = self.tables();
+        (0..tables.sections.routes.len).map(move |i| tables.string(tables.sections.routes, i))
+    }
+
+    fn tables(&self) -> Tables<'_> {
+        Tables {
+            bytes: self.bytes.as_ref(),
+            sections: self.sections,
+            constraints: &self.constraints,
+        }
+    }
+}
+
+/// The position of a section in the buffer.
+#[derive(Clone, Copy, Default)]
+struct Section {
+    // the offset of the section, in bytes
+    offset: usize,
+    // the number of elements in the section
+    len: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Sections {
+    nodes: Section,
+    children: Section,
+    remappings: Section,
+    routes: Section,
+    constraints: Section,
+    data: Section,
+}
+
+impl Sections {
+    fn parse(bytes: &[u8]) -> Result<Self, FrozenError> {
+        if bytes.len() < HEADER_LEN || !bytes.starts_with(MAGIC) {
+            return Err(FrozenError::InvalidHeader);
+        }
+
+        let header = |i: usize| read(bytes, MAGIC.len() + 4 * i) as usize;
+
+        if header(0) != VERSION as usize {
+            return Err(FrozenError::UnsupportedVersion {
+                version: header(0) as u32,
+            });
+        }
+
+        // the size of an element of each section
+        let sizes = [4 * NODE_WORDS, 4, 8, 8, 8, 1];
+        let mut sections = [Section::default(); 6];
+        let mut offset = HEADER_LEN;
+
+        for (i, (section, size)) in sections.iter_mut().zip(sizes).enumerate() {
+            let len = header(i + 1);
+            *section = Section { offset, len };
+
+            offset = len
+                .checked_mul(size)
+                .and_then(|len| len.checked_add(offset))
+                .ok_or(FrozenError::Corrupted)?;
+        }
+
+        // there must be a root node, and nothing after the data
+        if sections[0].len == 0 || offset != bytes.len() {
+            return Err(FrozenError::Corrupted);
+        }
+
+        let [nodes, children, remappings, routes, constraints, data] = sections;
+        Ok(Self {
+            nodes,
+            children,
+            remappings,
+            routes,
+            constraints,
+            data,
+        })
+    }
+}
+
+/// A view of the sections of a buffer.
+struct Tables<'b> {
+    bytes: &'b [u8],
+    sections: Sections,
+    constraints: &'b [Option<Constraint>],
+}
+
+impl<'b> Tables<'b> {
+    // Returns the nth word of an element of a section.
+    fn word(&self, section: Section, size: usize, i: usize, n: usize) -> usize {
+        read(self.bytes, section.offset + 4 * (size * i + n)) as usize
+    }
+
+    // Returns a range of the data section.
+    fn data(&self, start: usize, len: usize) -> &'b [u8] {
+        let start = self.sections.data.offset + start;
+        &self.bytes[start..start + len]
+    }
+
+    // Returns the nth string of a table of strings.
+    fn string(&self, section: Section, i: usize) -> &'b str {
+        let bytes = self.data(self.word(section, 2, i, 0), self.word(section, 2, i, 1));
+
+        // all strings are checked when the buffer is loaded
+        str::from_utf8(bytes).unwrap()
+    }
+
+    fn validate(&self) -> Result<(), FrozenError> {
+        let Sections {
+            nodes,
+            children,
+            remappings,
+            routes,
+            constraints,
+            data,
+        } = self.sections;
+
+        let range = |start: usize, len: usize, end: usize| match start.checked_add(len) {
+            Some(range_end) if range_end <= end => Ok(()),
+            _ => Err(FrozenError::Corrupted),
+        };
+
+        for section in [remappings, routes, constraints] {
+            for i in 0..section.len {
+                let (start, len) = (self.word(section, 2, i, 0), self.word(section, 2, i, 1));
+                range(start, len, data.len)?;
+                str::from_utf8(self.data(start, len)).map_err(|_| FrozenError::Corrupted)?;
+
+                // parameter keys start with their `:` or `*`
+                if section.offset == remappings.offset && len == 0 {
+                    return Err(FrozenError::Corrupted);
+                }
+            }
+        }
+
+        // the number of continuation bytes of a character split by the prefixes leading to
+        // each node, which is known once its parent has been checked
+        let mut pending = vec![None; nodes.len];
+
+        for index in 0..nodes.len {
+            let node = FrozenNode {
+                tables: self,
+                index,
+            };
+
+            for &(start, len, end) in &[
+                (PREFIX, PREFIX + 1, data.len),
+                (INDICES, INDICES + 1, data.len),
+                (CHILDREN, CHILDREN + 1, children.len),
+                (REMAPPING, REMAPPING + 1, remappings.len),
+            ] {
+                range(node.word(start), node.word(len), end)?;
+            }
+
+            let flags = node.word(FLAGS) as u32;
+            let constraint = node.word(CONSTRAINT);
+            if flags & !(TYPE_MASK | WILD_CHILD | HAS_VALUE) != 0
+                || (flags & HAS_VALUE != 0 && node.word(VALUE) >= routes.len)
+                || (constraint != NONE as usize && constraint >= constraints.len)
+            {
+                return Err(FrozenError::Corrupted);
+            }
+
+            // children come after their parent, so every lookup moves forward
+            for i in 0..node.children_len() {
+                let child = self.word(children, 1, node.word(CHILDREN) + i, 0);
+                if child <= index || child >= nodes.len {
+                    return Err(FrozenError::Corrupted);
+                }
+            }
+
+            // every static child has an index, and the wildcard child comes last
+            let statics = node
+                .children_len()
+                .checked_sub(usize::from(node.wild_child()))
+                .ok_or(FrozenError::Corrupted)?;
+
+            if node.indices().len() != statics
+                || (node.wild_child()
+                    && !matches!(
+                        node.child(statics).node_type(),
+                        NodeType::Param | NodeType::CatchAll
+                    ))
+            {
+                return Err(FrozenError::Corrupted);
+            }
+
+            let valid = match node.node_type() {
+                NodeType::Param | NodeType::CatchAll => {
+                    !node.prefix().is_empty() && str::from_utf8(&node.prefix()[1..]).is_ok()
+                }
+                // only the root of an empty tree has no prefix
+                NodeType::Static => !node.prefix().is_empty() || index == 0,
+                NodeType::Root => true,
+            } && (node.node_type() != NodeType::Param
+                // a parameter value ends where a character starts
+                || !node.indices().iter().any(|b| (0x80..=0xBF).contains(b)));
+
+            if !valid {
+                return Err(FrozenError::Corrupted);
+            }
+
+            // parameter values start and end on character boundaries of the path, so static
+            // prefixes may only split a character between a node and its static children
+            let split = match node.node_type() {
+                NodeType::Param | NodeType::CatchAll => match pending[index] {
+                    None | Some(0) => 0,
+                    Some(_) => return Err(FrozenError::Corrupted),
+                },
+                _ => continuation(pending[index].unwrap_or(0), node.prefix())
+                    .ok_or(FrozenError::Corrupted)?,
+            };
+
+            for i in 0..node.children_len() {
+                let child = node.child(i).index;
+                match pending[child] {
+                    Some(other) if other != split => return Err(FrozenError::Corrupted),
+                    _ => pending[child] = Some(split),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A node of a frozen router.
+#[derive(Clone, Copy)]
+struct FrozenNode<'t, 'b> {
+    tables: &'t Tables<'b>,
+    index: usize,
+}
+
+impl FrozenNode<'_, '_> {
+    // Returns the nth word of the node record.
+    fn word(self, n: usize) -> usize {
+        let nodes = self.tables.sections.nodes;
+        self.tables.word(nodes, NODE_WORDS, self.index, n)
+    }
+}
+
+impl<'t, 'b> NodeRef<'b> for FrozenNode<'t, 'b> {
+    fn prefix(self) -> &'b [u8] {
+        self.tables.data(self.word(PREFIX), self.word(PREFIX + 1))
+    }
+
+    fn indices(self) -> &'b [u8] {
+        self.tables.data(self.word(INDICES), self.word(INDICES + 1))
+    }
+
+    fn child(self, i: usize) -> Self {
+        let children = self.tables.sections.children;
+        let index = self.tables.word(children, 1, self.word(CHILDREN) + i, 0);
+
+        FrozenNode {
+            tables: self.tables,
+            index,
+        }
+    }
+
+    fn children_len(self) -> usize {
+        self.word(CHILDREN + 1)
+    }
+
+    fn wild_child(self) -> bool {
+        self.word(FLAGS) as u32 & WILD_CHILD != 0
+    }
+
+    fn node_type(self) -> NodeType {
+        match self.word(FLAGS) as u32 & TYPE_MASK {
+            0 => NodeType::Root,
+            1 => NodeType::Param,
+            2 => NodeType::CatchAll,
+            _ => NodeType::Static,
+        }
+    }
+
+    fn has_value(self) -> bool {
+        self.word(FLAGS) as u32 & HAS_VALUE != 0
+    }
+
+    fn accepts(self, value: &[u8]) -> bool {
+        let constraint = self.word(CONSTRAINT);
+        if constraint == NONE as usize {
+            return true;
+        }
+
+        // a constraint that was not registered again rejects every value
+        match (&self.tables.constraints[constraint], str::from_utf8(value)) {
+            (Some(constraint), Ok(value)) => constraint.matches(value),
+            _ => false,
+        }
+    }
+
+    fn param_key(self, i: usize) -> Option<&'b [u8]> {
+        if i >= self.word(REMAPPING + 1) {
+            return None;
+        }
+
+        let key = self
+            .tables
+            .string(self.tables.sections.remappings, self.word(REMAPPING) + i);
+        Some(&key.as_bytes()[1..])
+    }
+}
+
+/// Flattens the nodes of a tree into the sections of a buffer.
+#[derive(Default)]
+struct Writer<'n> {
+    // the words of each section, checked to fit in a `u32` once the tree is written
+    nodes: Vec<usize>,
+    children: Vec<usize>,
+    remappings: Vec<usize>,
+    data: Vec<u8>,
+    // the distinct constraints of the tree, along with their names
+    constraints: Vec<(&'n [u8], &'n Constraint)>,
+}
+
+impl<'n> Writer<'n> {
+    // Writes a node and its children in pre-order, returning the index of the node.
+    fn node(&mut self, node: &'n Node<usize>) -> usize {
+        let index = self.nodes.len() / NODE_WORDS;
+        self.nodes.resize(self.nodes.len() + NODE_WORDS, 0);
+
+        let prefix = self.data(node.prefix());
+        let indices = self.data(node.indices());
+
+        let remapping = [self.remappings.len() / 2, node.param_remapping.len()];
+        for key in &node.param_remapping {
+            let key = self.data(key);
+            self.remappings.extend_from_slice(&key);
+        }
+
+        let flags = match node.node_type() {
+            NodeType::Root => 0,
+            NodeType::Param => 1,
+            NodeType::CatchAll => 2,
+            NodeType::Static => 3,
+        } | if node.wild_child() { WILD_CHILD } else { 0 }
+            | if node.has_value() { HAS_VALUE } else { 0 };
+        let none = NONE as usize;
+
+        // SAFETY: We only expose &mut T through &mut self
+        let value = match node.value {
+            Some(ref value) => unsafe { *value.get() },
+            None => none,
+        };
+
+        let constraint = match node.constraint {
+            Some(ref constraint) => self.constraint(node.prefix(), constraint),
+            None => none,
+        };
+
+        // reserve the children of the node, they are filled in once their indices are known
+        let first = self.children.len();
+        self.children.resize(first + node.children.len(), 0);
+
+        for (i, child) in node.children.iter().enumerate() {
+            self.children[first + i] = self.node(child);
+        }
+
+        let record = [
+            prefix[0],
+            prefix[1],
+            indices[0],
+            indices[1],
+            first,
+            node.children.len(),
+            remapping[0],
+            remapping[1],
+            flags as usize,
+            value,
+            constraint,
+        ];
+
+        self.nodes[index * NODE_WORDS..(index + 1) * NODE_WORDS].copy_from_slice(&record);
+        index
+    }
+
+    // Returns the index of the constraint of a parameter node.
+    fn constraint(&mut self, prefix: &'n [u8], constraint: &'n Constraint) -> usize {
+        // the name was validated when the route was inserted
+        let (_, name) = split_constraint(prefix).unwrap();
+        let name = name.unwrap();
+
+        // the same name could have been registered again between insertions
+        self.constraints
+            .iter()
+            .position(|&(n, c)| n == name && c.same(constraint))
+            .unwrap_or_else(|| {
+                self.constraints.push((name, constraint));
+                self.constraints.len() - 1
+            })
+    }
+
+    // Appends bytes to the data section, returning their range.
+    fn data(&mut self, bytes: &[u8]) -> [usize; 2] {
+        let start = self.data.len();
+        self.data.extend_from_slice(bytes);
+        [start, bytes.len()]
+    }
+}
+
+// Returns the number of continuation bytes of a UTF-8 character still expected after `bytes`,
+// given the number expected before them, or `None` if they are not part of valid UTF-8.
+fn continuation(mut pending: u8, bytes: &[u8]) -> Option<u8> {
+    for &b in bytes {
+        pending = match (pending, b) {
+            (0, 0x00..=0x7F) => 0,
+            (0, 0xC0..=0xDF) => 1,
+            (0, 0xE0..=0xEF) => 2,
+            (0, 0xF0..=0xF7) => 3,
+            (n, 0x80..=0xBF) if n > 0 => n - 1,
+            _ => return None,
+        };
+    }
+
+    Some(pending)
+}
+
+// Reads the word at the given byte offset.
+fn read(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
@@ -0,0 +1,393 @@
+use crate::tree::{Constraint, Constraints};
+use crate::{InsertError, Match, MatchError, Params, Router};
+
+/// The name of the catch-all parameter a bare `*` label is stored under, it is never
+/// reported as a parameter.
+const ANY: &str = "__matchit_any_host";
+
+/// A URL router that matches the host of a request before its path.
+///
+/// Host patterns are matched label by label, from right to left, using the same syntax as
+/// routes with `.` in place of `/`. A named parameter like `:tenant.example.com` matches a
+/// single label, and a catch-all like `*.example.com` or `*sub.example.com` matches any number
+/// of labels, so it must be the leftmost label. Static labels take priority over parameters,
+/// and hosts are compared case-insensitively, ignoring any port.
+///
+/// The parameters of the host and the path are returned together, host parameters first.
+///
+/// ```rust
+/// use matchit::HostRouter;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut router = HostRouter::new();
+/// router.insert(":tenant.example.com", "/dashboard", "Dashboard")?;
+/// router.insert("api.example.com", "/users/:id", "A User")?;
+/// router.insert("*.example.org", "/", "Home")?;
+///
+/// let matched = router.at("acme.example.com", "/dashboard")?;
+/// assert_eq!(matched.params.get("tenant"), Some("acme"));
+/// assert_eq!(*matched.value, "Dashboard");
+///
+/// let matched = router.at("api.example.com:8080", "/users/978")?;
+/// assert_eq!(matched.params.get("id"), Some("978"));
+///
+/// assert_eq!(*router.at("a.b.example.org", "/")?.value, "Home");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct HostRouter<T> {
+    // the routes of each host, stored under the host pattern with its labels reversed
+    hosts: Router<Router<T>>,
+    constraints: Constraints,
+}
+
+impl<T> Default for HostRouter<T> {
+    fn default() -> Self {
+        Self {
+            hosts: Router::new(),
+            constraints: Constraints::new(),
+        }
+    }
+}
+
+impl<T> HostRouter<T> {
+    /// Construct a new router.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a named constraint for host and route parameters.
+    ///
+    /// See [`Router::constraint`] for details.
+    pub fn constraint(
+        &mut self,
+        name: impl Into<String>,
+        constraint: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        let (name, constraint) = (name.into(), Constraint::new(constraint));
+
+        register(&mut self.hosts, &name, &constraint);
+        for (_, router) in self.hosts.iter_mut() {
+            register(router, &name, &constraint);
+        }
+
+        self.constraints.insert(name, constraint);
+    }
+
+    /// Insert a route for the given host pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::HostRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = HostRouter::new();
+    /// router.insert("example.com", "/home", "Welcome!")?;
+    /// router.insert(":tenant.example.com", "/home", "Welcome back!")?;
+    ///
+    /// assert!(router.insert(":name.example.com", "/", "Again!").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(
+        &mut self,
+        host: impl Into<String>,
+        route: impl Into<String>,
+        value: T,
+    ) -> Result<(), InsertError> {
+        let pattern = host_route(&host.into())?;
+
+        if let Some(router) = self.hosts.route_mut(&pattern) {
+            return router.insert(route, value);
+        }
+
+        let mut router = Router::new();
+        for (name, constraint) in &self.constraints {
+            register(&mut router, name, constraint);
+        }
+
+        router.insert(route, value)?;
+        self.hosts.insert(pattern, router).map_err(host_error)
+    }
+
+    /// Removes a route of the given host pattern, returning its value if it was registered.
+    ///
+    /// Like [`Router::remove`], the host pattern and route must be passed in the same form
+    /// they were inserted in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::HostRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = HostRouter::new();
+    /// router.insert("example.com", "/home", "Welcome!")?;
+    ///
+    /// assert_eq!(router.remove("example.com", "/home"), Some("Welcome!"));
+    /// assert_eq!(router.remove("example.com", "/home"), None);
+    /// assert!(router.at("example.com", "/home").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, host: impl AsRef<str>, route: impl Into<String>) -> Option<T> {
+        let pattern = host_route(host.as_ref()).ok()?;
+        let router = self.hosts.route_mut(&pattern)?;
+        let value = router.remove(route)?;
+
+        // the host has no routes left
+        if router.iter().next().is_none() {
+            self.hosts.remove(pattern);
+        }
+
+        Some(value)
+    }
+
+    /// Tries to find the value of a route matching the given host and path.
+    ///
+    /// If no host pattern matches, [`MatchError::NotFound`] is returned. Otherwise, the path
+    /// is matched against the routes of the host.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::HostRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = HostRouter::new();
+    /// router.insert(":tenant.example.com", "/users/:id", "A User")?;
+    ///
+    /// let matched = router.at("acme.example.com", "/users/978")?;
+    /// assert!(matched.params.iter().eq([("tenant", "acme"), ("id", "978")]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at<'m, 'p>(
+        &'m self,
+        host: &'p str,
+        path: &'p str,
+    ) -> Result<Match<'m, 'p, &'m T>, MatchError> {
+        let host = ReversedHost::new(host);
+        let matched = self
+            .hosts
+            .at(&host.route)
+            .map_err(|_| MatchError::NotFound)?;
+
+        let mut params = host.params(&matched.params);
+        let Match {
+            value,
+            params: path_params,
+            query,
+            route,
+        } = matched.value.at(path)?;
+
+        for (key, value) in path_params.iter() {
+            params.push(key.as_bytes(), value.as_bytes());
+        }
+
+        Ok(Match {
+            value,
+            params,
+            query,
+            route,
+        })
+    }
+
+    /// Tries to find the value of a route matching the given host and path, returning a
+    /// mutable reference.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::HostRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = HostRouter::new();
+    /// router.insert("example.com", "/", 1)?;
+    ///
+    /// *router.at_mut("example.com", "/")?.value += 1;
+    /// assert_eq!(*router.at("example.com", "/")?.value, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at_mut<'m, 'p>(
+        &'m mut self,
+        host: &'p str,
+        path: &'p str,
+    ) -> Result<Match<'m, 'p, &'m mut T>, MatchError> {
+        let host = ReversedHost::new(host);
+        let matched = self
+            .hosts
+            .at_mut(&host.route)
+            .map_err(|_| MatchError::NotFound)?;
+
+        let mut params = host.params(&matched.params);
+        let Match {
+            value,
+            params: path_params,
+            query,
+            route,
+        } = matched.value.at_mut(path)?;
+
+        for (key, value) in path_params.iter() {
+            params.push(key.as_bytes(), value.as_bytes());
+        }
+
+        Ok(Match {
+            value,
+            params,
+            query,
+            route,
+        })
+    }
+}
+
+// Registers a constraint that was already created with another router.
+fn register<T>(router: &mut Router<T>, name: &str, constraint: &Constraint) {
+    let constraint = constraint.clone();
+    router.constraint(name, move |value| constraint.matches(value));
+}
+
+// Converts a host pattern into a route with its labels reversed, ex: `:tenant.example.com`
+// into `/com/example/:tenant`.
+fn host_route(host: &str) -> Result<String, InsertError> {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    let mut route = String::with_capacity(host.len() + 1);
+
+    for label in host.rsplit('.') {
+        route.push('/');
+
+        if label == "*" {
+            route.push('*');
+            route.push_str(ANY);
+            continue;
+        }
+
+        // a catch-all must take up the whole label, its value is made up of entire labels,
+        // and a doubled `*` would otherwise be read as an escaped literal
+        if matches!(label.rfind('*'), Some(i) if i > 0) {
+            return Err(InsertError::InvalidCatchAll);
+        }
+
+        // parameter names are case-sensitive
+        if label.contains(':') || label.starts_with('*') {
+            route.push_str(label);
+        } else {
+            route.push_str(&label.to_ascii_lowercase());
+        }
+    }
+
+    Ok(route)
+}
+
+// Converts a route created by `host_route` back into a host pattern.
+fn host_pattern(route: &str) -> String {
+    let route = route.strip_prefix('/').unwrap_or(route);
+    route
+        .rsplit('/')
+        .map(host_label)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn host_label(label: &str) -> &str {
+    match label.strip_prefix('*') {
+        Some(ANY) => "*",
+        _ => label,
+    }
+}
+
+// Reports a conflict between host patterns rather than their routes.
+fn host_error(err: InsertError) -> InsertError {
+    match err {
+        InsertError::Conflict {
+            route,
+            with,
+            segment,
+            kind,
+        } => InsertError::Conflict {
+            route: host_pattern(&route),
+            with: host_pattern(&with),
+            segment: host_label(&segment).to_owned(),
+            kind,
+        },
+        err => err,
+    }
+}
+
+/// A host with its labels reversed into a route, ex: `acme.example.com` into
+/// `/com/example/acme`.
+struct ReversedHost<'p> {
+    host: &'p str,
+    route: String,
+    // the start of each label in the route, and in the host
+    labels: Vec<(usize, usize)>,
+}
+
+impl<'p> ReversedHost<'p> {
+    fn new(host: &'p str) -> Self {
+        // strip the port, without mistaking the last part of an IPv6 address for one
+        let host = match host.rfind(':') {
+            Some(i)
+                if host[i + 1..].bytes().all(|c| c.is_ascii_digit())
+                    && (host[..i].ends_with(']') || !host[..i].contains(':')) =>
+            {
+                &host[..i]
+            }
+            _ => host,
+        };
+        let host = host.strip_suffix('.').unwrap_or(host);
+
+        let mut route = String::with_capacity(host.len() + 1);
+        let mut labels = Vec::new();
+        let mut end = host.len();
+
+        for label in host.rsplit('.') {
+            route.push('/');
+            labels.push((route.len(), end - label.len()));
+            route.push_str(label);
+            end = end.saturating_sub(label.len() + 1);
+        }
+
+        route.make_ascii_lowercase();
+        Self {
+            host,
+            route,
+            labels,
+        }
+    }
+
+    // Maps the parameters matched against the route to the labels of the host.
+    fn params<'k>(&self, params: &Params<'k, '_>) -> Params<'k, 'p> {
+        let mut host_params = Params::new();
+
+        for (key, value) in params.iter() {
+            if key != ANY {
+                host_params.push(key.as_bytes(), self.original(value).as_bytes());
+            }
+        }
+
+        host_params
+    }
+
+    // Returns the part of the host that a value of the route was matched from.
+    fn original(&self, value: &str) -> &'p str {
+        // the value of an optional catch-all is not part of the route
+        if value.is_empty() {
+            return "";
+        }
+
+        let start = value.as_ptr() as usize - self.route.as_ptr() as usize;
+        let end = start + value.len();
+
+        let i = self.labels.iter().rposition(|&(r, _)| r <= start).unwrap();
+        let (label, original) = self.labels[i];
+
+        match self.labels.get(i + 1) {
+            // a catch-all, from the leftmost label up to the end of this one
+            Some(&(next, _)) if end >= next => {
+                let (_, leftmost) = self.labels[self.labels.len() - 1];
+                &self.host[leftmost..original + next - 1 - label]
+            }
+            _ => &self.host[original + start - label..original + end - label],
+        }
+    }
+}
@@ -43,6 +43,41 @@
 //! # }
 //! ```
 //!
+//! The name of a parameter ends at the first character that is not alphanumeric or `_`. Any text
+//! after it is matched as a static suffix, so a single segment can hold multiple parameters. The
+//! value of a parameter ends at the first occurrence of its suffix that leads to a match:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.insert("/files/:name.:ext", true)?;
+//!
+//! let matched = m.at("/files/archive.tar.gz")?;
+//! assert_eq!(matched.params.get("name"), Some("archive"));
+//! assert_eq!(matched.params.get("ext"), Some("tar.gz"));
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Routes can name the parameters they share differently. A match always uses the names of the
+//! route that matched:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.insert("/users/:id", true)?;
+//! m.insert("/users/:user_id/posts", true)?;
+//!
+//! assert_eq!(m.at("/users/1")?.params.get("id"), Some("1"));
+//! assert_eq!(m.at("/users/1/posts")?.params.get("user_id"), Some("1"));
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ### Catch-all Parameters
 //!
 //! Catch-all parameters start with `*` and match anything until the end of the path.
@@ -64,6 +99,83 @@
 //! # }
 //! ```
 //!
+//! A catch-all parameter followed by `?` is optional, and also matches the bare prefix with an
+//! empty value:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.insert("/static/*path?", true)?;
+//!
+//! assert_eq!(m.at("/static/")?.params.get("path"), Some(""));
+//! assert_eq!(m.at("/static/app.js")?.params.get("path"), Some("app.js"));
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ### Optional Parameters
+//!
+//! Trailing named parameters followed by `?` are optional. The route also matches without them,
+//! sharing the same value:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.insert("/posts/:page?", true)?;
+//!
+//! assert!(m.at("/posts")?.params.is_empty());
+//! assert_eq!(m.at("/posts/2")?.params.get("page"), Some("2"));
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A `?` that doesn't follow a parameter, like in `/search?`, is matched as static text.
+//!
+//! ### Constraints
+//!
+//! Named parameters can be restricted to values accepted by a constraint, registered with
+//! [`Router::constraint`]. If a value is rejected, matching continues with the other routes:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.constraint("num", |id| id.parse::<u64>().is_ok());
+//! m.insert("/users/:id<num>", "A User")?;
+//! m.insert("/:page/me", "Me")?;
+//!
+//! assert_eq!(*m.at("/users/1")?.value, "A User");
+//! assert_eq!(*m.at("/users/me")?.value, "Me");
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Parameters with different constraints can't share a position, so a rejected value never
+//! falls back to another parameter there. See [`Router::constraint`] for details.
+//!
+//! ### Escaping
+//!
+//! A literal `:` or `*` can be matched by doubling it:
+//!
+//! ```rust
+//! # use matchit::Router;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = Router::new();
+//! m.insert("/v1/projects/:id::batchGet", true)?;
+//! m.insert("/files/**.tar", true)?;
+//!
+//! assert_eq!(m.at("/v1/projects/abc:batchGet")?.params.get("id"), Some("abc"));
+//! assert!(m.at("/files/*.tar").is_ok());
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Routing Priority
 //!
 //! Static and dynamic route segments are allowed to overlap. If they do, static segments will be given higher priority:
@@ -102,14 +214,33 @@
 //! by the number of children with registered values, increasing the chance of choosing the correct branch of the first try.
 #![deny(rust_2018_idioms, clippy::all)]
 
+#[cfg(feature = "serde")]
+mod de;
 mod error;
+mod frozen;
+mod host;
+mod method;
 mod params;
+mod path;
+mod query;
 mod router;
+#[cfg(feature = "tower")]
+mod service;
 mod tree;
 
-pub use error::{InsertError, MatchError};
-pub use params::{Params, ParamsIter};
-pub use router::{Match, Router};
+pub use error::{
+    ConflictKind, DecodeError, FrozenError, InsertError, MatchError, MergeError, MethodError,
+    ParamError, UrlError,
+};
+pub use frozen::FrozenRouter;
+pub use host::HostRouter;
+pub use method::MethodRouter;
+pub use params::{OwnedParams, OwnedParamsIter, Params, ParamsIter};
+pub use path::clean_path;
+pub use query::{Query, QueryIter};
+pub use router::{IntoIter, Iter, IterMut, Match, Router};
+#[cfg(feature = "tower")]
+pub use service::{ResponseFuture, RouterService};
 
 #[cfg(doctest)]
 mod test_readme {
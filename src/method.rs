@@ -0,0 +1,284 @@
+use crate::{InsertError, Match, MatchError, MethodError, Router};
+
+/// A URL router that stores a value for each HTTP method of a route.
+///
+/// Unlike a separate [`Router`] per method, a failed match can tell a path that
+/// doesn't exist apart from one that exists under other methods.
+///
+/// ```rust
+/// use matchit::{MethodError, MethodRouter};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut router = MethodRouter::new();
+/// router.insert("GET", "/users/:id", "Get User")?;
+/// router.insert("DELETE", "/users/:id", "Delete User")?;
+///
+/// let matched = router.at("GET", "/users/978")?;
+/// assert_eq!(matched.params.get("id"), Some("978"));
+/// assert_eq!(*matched.value, "Get User");
+///
+/// // the route exists, but not for this method
+/// if let Err(MethodError::MethodNotAllowed { allowed }) = router.at("POST", "/users/978") {
+///     assert_eq!(allowed, ["GET", "DELETE"]);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct MethodRouter<T> {
+    // the values of a route, in the order their methods were inserted
+    router: Router<Vec<(String, T)>>,
+}
+
+impl<T> Default for MethodRouter<T> {
+    fn default() -> Self {
+        Self {
+            router: Router::new(),
+        }
+    }
+}
+
+impl<T> MethodRouter<T> {
+    /// Construct a new router.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a named constraint for route parameters.
+    ///
+    /// See [`Router::constraint`] for details.
+    pub fn constraint(
+        &mut self,
+        name: impl Into<String>,
+        f: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        self.router.constraint(name, f);
+    }
+
+    /// Insert a route for the given method.
+    ///
+    /// Methods are compared case-sensitively. A route can be inserted for any number
+    /// of methods, but only once for each.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::MethodRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = MethodRouter::new();
+    /// router.insert("GET", "/home", "Welcome!")?;
+    /// router.insert("POST", "/home", "Posted!")?;
+    ///
+    /// assert!(router.insert("GET", "/home", "Again!").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(
+        &mut self,
+        method: impl Into<String>,
+        route: impl Into<String>,
+        value: T,
+    ) -> Result<(), InsertError> {
+        let (method, route) = (method.into(), route.into());
+
+        let values = match self.router.route_mut(&route) {
+            Some(values) => values,
+            None => return self.router.insert(route, vec![(method, value)]),
+        };
+
+        if values.iter().any(|(m, _)| *m == method) {
+            return Err(InsertError::duplicate(route));
+        }
+
+        values.push((method, value));
+        Ok(())
+    }
+
+    /// Removes the value of a route for the given method, returning it if it was registered.
+    ///
+    /// Like [`Router::remove`], the route must be passed in the same form it was inserted in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::MethodRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = MethodRouter::new();
+    /// router.insert("GET", "/home", "Welcome!")?;
+    ///
+    /// assert_eq!(router.remove("GET", "/home"), Some("Welcome!"));
+    /// assert_eq!(router.remove("GET", "/home"), None);
+    /// assert!(router.at("GET", "/home").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, method: impl AsRef<str>, route: impl Into<String>) -> Option<T> {
+        let route = route.into();
+        let values = self.router.route_mut(&route)?;
+
+        let i = values.iter().position(|(m, _)| m == method.as_ref())?;
+        let (_, value) = values.remove(i);
+
+        // the route has no methods left
+        if values.is_empty() {
+            self.router.remove(route);
+        }
+
+        Some(value)
+    }
+
+    /// Tries to find the value of a route matching the given method and path.
+    ///
+    /// Routes that match the path but were not registered for the method are skipped, so
+    /// the most specific route registered for the method is found. If there is none,
+    /// [`MethodError::MethodNotAllowed`] is returned with the methods of every route that
+    /// matches the path. These can be used for the `Allow` header of the response, or to
+    /// answer an `OPTIONS` request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::MethodRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = MethodRouter::new();
+    /// router.insert("GET", "/home", "Welcome!")?;
+    ///
+    /// let matched = router.at("GET", "/home")?;
+    /// assert_eq!(*matched.value, "Welcome!");
+    ///
+    /// let err = router.at("OPTIONS", "/home").unwrap_err();
+    /// assert_eq!(err.allowed(), Some(&["GET".to_owned()][..]));
+    ///
+    /// // a less specific route registered for the method
+    /// router.insert("POST", "/:page", "Posted!")?;
+    /// assert_eq!(*router.at("POST", "/home")?.value, "Posted!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at<'m, 'p>(
+        &'m self,
+        method: impl AsRef<str>,
+        path: &'p str,
+    ) -> Result<Match<'m, 'p, &'m T>, MethodError> {
+        let method = method.as_ref();
+        let mut allowed = Vec::new();
+
+        let Match {
+            value,
+            params,
+            query,
+            route,
+        } = self
+            .router
+            .at_where(path, |values| allows(values, method, &mut allowed))
+            .map_err(|err| MethodError::not_allowed(err, allowed))?;
+
+        // the route was registered for the method
+        let (_, value) = value.iter().find(|(m, _)| m == method).unwrap();
+        Ok(Match {
+            value,
+            params,
+            query,
+            route,
+        })
+    }
+
+    /// Tries to find the value of a route matching the given method and path,
+    /// returning a mutable reference.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::MethodRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = MethodRouter::new();
+    /// router.insert("GET", "/", 1)?;
+    ///
+    /// *router.at_mut("GET", "/")?.value += 1;
+    /// assert_eq!(*router.at("GET", "/")?.value, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at_mut<'m, 'p>(
+        &'m mut self,
+        method: impl AsRef<str>,
+        path: &'p str,
+    ) -> Result<Match<'m, 'p, &'m mut T>, MethodError> {
+        let method = method.as_ref();
+        let mut allowed = Vec::new();
+
+        let Match {
+            value,
+            params,
+            query,
+            route,
+        } = self
+            .router
+            .at_mut_where(path, |values| allows(values, method, &mut allowed))
+            .map_err(|err| MethodError::not_allowed(err, allowed))?;
+
+        // the route was registered for the method
+        let (_, value) = value.iter_mut().find(|(m, _)| m == method).unwrap();
+        Ok(Match {
+            value,
+            params,
+            query,
+            route,
+        })
+    }
+
+    /// Returns the methods registered for the routes matching the given path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::MethodRouter;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = MethodRouter::new();
+    /// router.insert("GET", "/users/:id", "Get User")?;
+    /// router.insert("PUT", "/users/:id", "Update User")?;
+    ///
+    /// router.insert("DELETE", "/:page/1", "Delete Page")?;
+    ///
+    /// assert!(router.allowed("/users/1")?.eq(["GET", "PUT", "DELETE"]));
+    /// assert!(router.allowed("/posts/1")?.eq(["DELETE"]));
+    /// assert!(router.allowed("/posts/2").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn allowed(&self, path: &str) -> Result<impl Iterator<Item = &str>, MatchError> {
+        let mut allowed = Vec::new();
+
+        // reject every route, so that all of the matching ones are visited
+        let matched = self.router.at_where(path, |values| {
+            for (method, _) in values {
+                if !allowed.contains(&method.as_str()) {
+                    allowed.push(method.as_str());
+                }
+            }
+
+            false
+        });
+
+        match matched {
+            Err(err) if allowed.is_empty() => Err(err),
+            _ => Ok(allowed.into_iter()),
+        }
+    }
+}
+
+// Returns `true` if a route was registered for the method, otherwise adds its methods
+// to the allowed ones.
+fn allows<T>(values: &[(String, T)], method: &str, allowed: &mut Vec<String>) -> bool {
+    if values.iter().any(|(m, _)| m == method) {
+        return true;
+    }
+
+    for (m, _) in values {
+        if !allowed.contains(m) {
+            allowed.push(m.clone());
+        }
+    }
+
+    false
+}
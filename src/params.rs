@@ -1,6 +1,12 @@
-use std::iter;
+use crate::path::percent_decode;
+use crate::{DecodeError, ParamError};
+
+use std::borrow::Cow;
+use std::fmt::Display;
 use std::mem;
+use std::ops::Range;
 use std::slice;
+use std::str::FromStr;
 
 /// A single URL parameter, consisting of a key and a value.
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Default, Copy, Clone)]
@@ -41,17 +47,74 @@ impl<'k, 'v> Param<'k, 'v> {
 /// ```
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Clone)]
 pub struct Params<'k, 'v> {
-    kind: ParamsKind<'k, 'v>,
+    kind: ParamsKind<Param<'k, 'v>>,
 }
 
 // most routes have 1-3 dynamic parameters, so we can avoid a heap allocation in common cases.
 const SMALL: usize = 3;
 
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Clone)]
-enum ParamsKind<'k, 'v> {
+enum ParamsKind<P> {
     None,
-    Small([Param<'k, 'v>; SMALL], usize),
-    Large(Vec<Param<'k, 'v>>),
+    Small([P; SMALL], usize),
+    Large(Vec<P>),
+}
+
+impl<P: Default> ParamsKind<P> {
+    fn len(&self) -> usize {
+        match self {
+            ParamsKind::None => 0,
+            ParamsKind::Small(_, len) => *len,
+            ParamsKind::Large(vec) => vec.len(),
+        }
+    }
+
+    fn as_slice(&self) -> &[P] {
+        match self {
+            ParamsKind::None => &[],
+            ParamsKind::Small(arr, len) => &arr[..*len],
+            ParamsKind::Large(vec) => vec,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [P] {
+        match self {
+            ParamsKind::None => &mut [],
+            ParamsKind::Small(arr, len) => &mut arr[..*len],
+            ParamsKind::Large(vec) => vec,
+        }
+    }
+
+    fn push(&mut self, param: P) {
+        #[cold]
+        fn drain_to_vec<T: Default>(len: usize, elem: T, arr: &mut [T; SMALL]) -> Vec<T> {
+            let mut vec = Vec::with_capacity(len + 1);
+            vec.extend(arr.iter_mut().map(mem::take));
+            vec.push(elem);
+            vec
+        }
+
+        match self {
+            ParamsKind::None => {
+                *self = ParamsKind::Small([param, P::default(), P::default()], 1);
+            }
+            ParamsKind::Small(arr, len) => {
+                if *len == SMALL {
+                    *self = ParamsKind::Large(drain_to_vec(*len, param, arr));
+                    return;
+                }
+                arr[*len] = param;
+                *len += 1;
+            }
+            ParamsKind::Large(vec) => vec.push(param),
+        }
+    }
+}
+
+impl<'k, 'v> Default for Params<'k, 'v> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'k, 'v> Params<'k, 'v> {
@@ -63,11 +126,7 @@ impl<'k, 'v> Params<'k, 'v> {
 
     /// Returns the number of parameters.
     pub fn len(&self) -> usize {
-        match &self.kind {
-            ParamsKind::None => 0,
-            ParamsKind::Small(_, len) => *len,
-            ParamsKind::Large(vec) => vec.len(),
-        }
+        self.kind.len()
     }
 
     pub(crate) fn truncate(&mut self, n: usize) {
@@ -86,18 +145,79 @@ impl<'k, 'v> Params<'k, 'v> {
     pub fn get(&self, key: impl AsRef<str>) -> Option<&'v str> {
         let key = key.as_ref().as_bytes();
 
-        match &self.kind {
-            ParamsKind::None => None,
-            ParamsKind::Small(arr, len) => arr
-                .iter()
-                .take(*len)
-                .find(|param| param.key == key)
-                .map(Param::value_str),
-            ParamsKind::Large(vec) => vec
-                .iter()
-                .find(|param| param.key == key)
-                .map(Param::value_str),
-        }
+        self.kind
+            .as_slice()
+            .iter()
+            .find(|param| param.key == key)
+            .map(Param::value_str)
+    }
+
+    /// Returns the value of the first parameter registered under the given key, with
+    /// any percent-escapes decoded.
+    ///
+    /// Values are decoded after the path is matched, so an encoded `/` (`%2F`) is part of
+    /// the value and never separates path segments.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut router = matchit::Router::new();
+    /// # router.insert("/users/:name", true).unwrap();
+    /// let matched = router.at("/users/John%20Doe%2FJane")?;
+    ///
+    /// assert_eq!(matched.params.get("name"), Some("John%20Doe%2FJane"));
+    /// assert_eq!(matched.params.get_decoded("name").unwrap()?, "John Doe/Jane");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_decoded(&self, key: impl AsRef<str>) -> Option<Result<Cow<'v, str>, DecodeError>> {
+        self.get(key).map(percent_decode)
+    }
+
+    /// Parses the value of the first parameter registered under the given key.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut router = matchit::Router::new();
+    /// # router.insert("/users/:id", true).unwrap();
+    /// let matched = router.at("/users/978")?;
+    ///
+    /// let id: u64 = matched.params.parse("id")?;
+    /// assert_eq!(id, 978);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse<T>(&self, key: impl AsRef<str>) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let key = key.as_ref();
+        parse_value(key, self.get(key))
+    }
+
+    /// Deserializes the parameters into any type implementing [`Deserialize`](serde::Deserialize).
+    ///
+    /// Parameters can be deserialized into structs and maps by key, or into tuples and
+    /// sequences in the order they appear in the route. A single parameter can also be
+    /// deserialized directly into its value.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut router = matchit::Router::new();
+    /// # router.insert("/:org/:repo/issues/:id", true).unwrap();
+    /// let matched = router.at("/rust-lang/rust/issues/1")?;
+    ///
+    /// let (org, repo, id): (String, &str, u32) = matched.params.deserialize()?;
+    /// assert_eq!((org.as_str(), repo, id), ("rust-lang", "rust", 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T>(&self) -> Result<T, ParamError>
+    where
+        T: serde::Deserialize<'v>,
+    {
+        T::deserialize(crate::de::ParamsDeserializer::new(self))
     }
 
     /// Returns an iterator over the parameters in the list.
@@ -107,97 +227,219 @@ impl<'k, 'v> Params<'k, 'v> {
 
     /// Returns `true` if there are no parameters in the list.
     pub fn is_empty(&self) -> bool {
-        match &self.kind {
-            ParamsKind::None => true,
-            ParamsKind::Small(_, len) => *len == 0,
-            ParamsKind::Large(vec) => vec.is_empty(),
-        }
+        self.kind.len() == 0
+    }
+
+    /// Copies the parameters into an [`OwnedParams`], that doesn't borrow from the
+    /// router or the path.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut router = matchit::Router::new();
+    /// # router.insert("/users/:id", true).unwrap();
+    /// let params = {
+    ///     let path = String::from("/users/1");
+    ///     router.at(&path)?.params.into_owned()
+    /// };
+    ///
+    /// assert_eq!(params.get("id"), Some("1"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_owned(self) -> OwnedParams {
+        OwnedParams::from(&self)
     }
 
     /// Inserts a key value parameter pair into the list.
     pub(crate) fn push(&mut self, key: &'k [u8], value: &'v [u8]) {
-        #[cold]
-        fn drain_to_vec<T: Default>(len: usize, elem: T, arr: &mut [T; SMALL]) -> Vec<T> {
-            let mut vec = Vec::with_capacity(len + 1);
-            vec.extend(arr.iter_mut().map(mem::take));
-            vec.push(elem);
-            vec
-        }
-
-        let param = Param { key, value };
-        match &mut self.kind {
-            ParamsKind::None => {
-                self.kind = ParamsKind::Small([param, Param::default(), Param::default()], 1);
-            }
-            ParamsKind::Small(arr, len) => {
-                if *len == SMALL {
-                    self.kind = ParamsKind::Large(drain_to_vec(*len, param, arr));
-                    return;
-                }
-                arr[*len] = param;
-                *len += 1;
-            }
-            ParamsKind::Large(vec) => vec.push(param),
-        }
+        self.kind.push(Param { key, value });
     }
 
     // Transform each key.
     pub(crate) fn for_each_key_mut(&mut self, f: impl Fn((usize, &mut &'k [u8]))) {
-        match &mut self.kind {
-            ParamsKind::None => {}
-            ParamsKind::Small(arr, len) => arr
-                .iter_mut()
-                .take(*len)
-                .map(|param| &mut param.key)
-                .enumerate()
-                .for_each(f),
-            ParamsKind::Large(vec) => vec
-                .iter_mut()
-                .map(|param| &mut param.key)
-                .enumerate()
-                .for_each(f),
-        }
+        self.kind
+            .as_mut_slice()
+            .iter_mut()
+            .map(|param| &mut param.key)
+            .enumerate()
+            .for_each(f)
     }
 }
 
 /// An iterator over the keys and values of a route's [parameters](crate::Params).
 pub struct ParamsIter<'ps, 'k, 'v> {
-    kind: ParamsIterKind<'ps, 'k, 'v>,
+    iter: slice::Iter<'ps, Param<'k, 'v>>,
 }
 
 impl<'ps, 'k, 'v> ParamsIter<'ps, 'k, 'v> {
     fn new(params: &'ps Params<'k, 'v>) -> Self {
-        let kind = match &params.kind {
-            ParamsKind::None => ParamsIterKind::None,
-            ParamsKind::Small(arr, len) => ParamsIterKind::Small(arr.iter().take(*len)),
-            ParamsKind::Large(vec) => ParamsIterKind::Large(vec.iter()),
-        };
-        Self { kind }
+        let iter = params.kind.as_slice().iter();
+        Self { iter }
     }
 }
 
-enum ParamsIterKind<'ps, 'k, 'v> {
-    None,
-    Small(iter::Take<slice::Iter<'ps, Param<'k, 'v>>>),
-    Large(slice::Iter<'ps, Param<'k, 'v>>),
-}
-
 impl<'ps, 'k, 'v> Iterator for ParamsIter<'ps, 'k, 'v> {
     type Item = (&'k str, &'v str);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.kind {
-            ParamsIterKind::None => None,
-            ParamsIterKind::Small(ref mut iter) => {
-                iter.next().map(|p| (p.key_str(), p.value_str()))
-            }
-            ParamsIterKind::Large(ref mut iter) => {
-                iter.next().map(|p| (p.key_str(), p.value_str()))
-            }
+        self.iter.next().map(|p| (p.key_str(), p.value_str()))
+    }
+}
+
+/// An owned list of parameters, that outlives the router and path it was matched from.
+///
+/// The keys and values are copied into a single buffer, so converting a borrowed list of
+/// [`Params`] takes a single allocation in the common case of 1-3 parameters.
+///
+/// ```rust
+/// # use matchit::OwnedParams;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut router = matchit::Router::new();
+/// # router.insert("/users/:id", true).unwrap();
+/// let params = {
+///     let path = String::from("/users/1");
+///     OwnedParams::from(&router.at(&path)?.params)
+/// };
+///
+/// assert_eq!(params.get("id"), Some("1"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedParams {
+    // the keys and values of every parameter, back to back
+    buf: String,
+    kind: ParamsKind<OwnedParam>,
+}
+
+/// A single owned URL parameter, as the positions of its key and value in the buffer.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+struct OwnedParam {
+    key: Range<usize>,
+    value: Range<usize>,
+}
+
+impl Default for OwnedParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OwnedParams {
+    /// Create a new empty list of parameters.
+    pub fn new() -> Self {
+        Self {
+            buf: String::new(),
+            kind: ParamsKind::None,
+        }
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.kind.len()
+    }
+
+    /// Returns `true` if there are no parameters in the list.
+    pub fn is_empty(&self) -> bool {
+        self.kind.len() == 0
+    }
+
+    /// Returns the value of the first parameter registered under the given key.
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&str> {
+        let key = key.as_ref();
+        self.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    /// Returns the value of the first parameter registered under the given key, with
+    /// any percent-escapes decoded.
+    ///
+    /// See [`Params::get_decoded`] for details.
+    pub fn get_decoded(&self, key: impl AsRef<str>) -> Option<Result<Cow<'_, str>, DecodeError>> {
+        self.get(key).map(percent_decode)
+    }
+
+    /// Parses the value of the first parameter registered under the given key.
+    ///
+    /// See [`Params::parse`] for details.
+    pub fn parse<T>(&self, key: impl AsRef<str>) -> Result<T, ParamError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let key = key.as_ref();
+        parse_value(key, self.get(key))
+    }
+
+    /// Returns an iterator over the parameters in the list.
+    pub fn iter(&self) -> OwnedParamsIter<'_> {
+        OwnedParamsIter {
+            buf: &self.buf,
+            iter: self.kind.as_slice().iter(),
         }
     }
 }
 
+impl From<&Params<'_, '_>> for OwnedParams {
+    fn from(params: &Params<'_, '_>) -> Self {
+        let size = params.iter().map(|(k, v)| k.len() + v.len()).sum();
+        let mut owned = OwnedParams {
+            buf: String::with_capacity(size),
+            kind: ParamsKind::None,
+        };
+
+        for (key, value) in params.iter() {
+            let start = owned.buf.len();
+            owned.buf.push_str(key);
+            let mid = owned.buf.len();
+            owned.buf.push_str(value);
+
+            owned.kind.push(OwnedParam {
+                key: start..mid,
+                value: mid..owned.buf.len(),
+            });
+        }
+
+        owned
+    }
+}
+
+impl From<Params<'_, '_>> for OwnedParams {
+    fn from(params: Params<'_, '_>) -> Self {
+        OwnedParams::from(&params)
+    }
+}
+
+/// An iterator over the keys and values of an [owned list of parameters](crate::OwnedParams).
+pub struct OwnedParamsIter<'ps> {
+    buf: &'ps str,
+    iter: slice::Iter<'ps, OwnedParam>,
+}
+
+impl<'ps> Iterator for OwnedParamsIter<'ps> {
+    type Item = (&'ps str, &'ps str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let param = self.iter.next()?;
+        Some((&self.buf[param.key.clone()], &self.buf[param.value.clone()]))
+    }
+}
+
+// parses the value of a parameter, if it exists
+fn parse_value<T>(key: &str, value: Option<&str>) -> Result<T, ParamError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = value.ok_or_else(|| ParamError::Missing {
+        key: key.to_owned(),
+    })?;
+
+    value.parse().map_err(|err: T::Err| ParamError::Invalid {
+        key: key.to_owned(),
+        message: err.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -254,4 +496,59 @@ mod tests {
         let params = Params::new();
         assert!(params.get("").is_none());
     }
+
+    #[test]
+    fn owned() {
+        let vec = vec![("hello", "hello"), ("world", "world"), ("baz", "baz")];
+
+        let mut params = Params::new();
+        for (key, value) in vec.clone() {
+            params.push(key.as_bytes(), value.as_bytes());
+        }
+
+        let owned = params.into_owned();
+        match owned.kind {
+            ParamsKind::Small(..) => {}
+            _ => panic!(),
+        }
+
+        assert_eq!(owned.buf, "hellohelloworldworldbazbaz");
+        assert_eq!(owned.len(), 3);
+        assert_eq!(owned.get("world"), Some("world"));
+        assert_eq!(owned.get("foo"), None);
+        assert!(owned.iter().eq(vec));
+    }
+
+    #[test]
+    fn owned_heap_alloc() {
+        let vec = vec![
+            ("hello", "1"),
+            ("world", "2"),
+            ("foo", "3"),
+            ("bar", "4"),
+            ("baz", "5"),
+        ];
+
+        let mut params = Params::new();
+        for (key, value) in vec.clone() {
+            params.push(key.as_bytes(), value.as_bytes());
+        }
+
+        let owned = OwnedParams::from(&params);
+        match owned.kind {
+            ParamsKind::Large(..) => {}
+            _ => panic!(),
+        }
+
+        assert_eq!(owned.parse::<u32>("bar"), Ok(4));
+        assert!(owned.iter().eq(params.iter()));
+    }
+
+    #[test]
+    fn owned_empty() {
+        let owned = Params::new().into_owned();
+        assert_eq!(owned, OwnedParams::new());
+        assert!(owned.is_empty());
+        assert_eq!(owned.iter().next(), None);
+    }
 }
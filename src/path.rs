@@ -0,0 +1,189 @@
+use crate::DecodeError;
+
+use std::borrow::Cow;
+
+/// Returns the canonical form of a URL path.
+///
+/// This is a port of `CleanPath` from [httprouter](https://github.com/julienschmidt/httprouter).
+/// The following rules are applied until no further processing can be done:
+///
+/// 1. Replace multiple slashes with a single slash.
+/// 2. Eliminate each `.` path name element (the current directory).
+/// 3. Eliminate each inner `..` path name element (the parent directory)
+///    along with the non-`..` element that precedes it.
+/// 4. Eliminate `..` elements that begin a rooted path, that is, replace `/..` by `/`
+///    at the beginning of a path.
+///
+/// The returned path always starts with a `/`. A trailing slash is kept if the path had one,
+/// or if it ended with a `.` element, but not after a resolved `..` element.
+///
+/// ```rust
+/// use matchit::clean_path;
+///
+/// assert_eq!(clean_path("/users//ferris/./posts/../"), "/users/ferris/");
+/// assert_eq!(clean_path("../../etc/passwd"), "/etc/passwd");
+/// assert_eq!(clean_path("/users/ferris/.."), "/users");
+/// assert_eq!(clean_path(""), "/");
+/// ```
+pub fn clean_path(path: &str) -> String {
+    let mut segments = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let trailing_slash = path.ends_with('/') || path.ends_with("/.");
+
+    let mut cleaned = String::with_capacity(path.len() + 1);
+    for segment in segments {
+        cleaned.push('/');
+        cleaned.push_str(segment);
+    }
+
+    if cleaned.is_empty() || trailing_slash {
+        cleaned.push('/');
+    }
+
+    cleaned
+}
+
+/// Decodes the percent-escapes in a URL path segment.
+///
+/// `+` is left as is, as it only stands for a space in query strings.
+pub(crate) fn percent_decode(value: &str) -> Result<Cow<'_, str>, DecodeError> {
+    // nothing to decode, avoid allocating
+    if !value.contains('%') {
+        return Ok(Cow::Borrowed(value));
+    }
+
+    let mut bytes = value.bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            decoded.push(b);
+            continue;
+        }
+
+        match (hex_digit(bytes.next()), hex_digit(bytes.next())) {
+            (Some(high), Some(low)) => decoded.push(high << 4 | low),
+            _ => return Err(DecodeError::InvalidEscape),
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map(Cow::Owned)
+        .map_err(|_| DecodeError::InvalidUtf8)
+}
+
+fn hex_digit(b: Option<u8>) -> Option<u8> {
+    (b? as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean() {
+        let tests = [
+            // already clean
+            ("/", "/"),
+            ("/abc", "/abc"),
+            ("/a/b/c", "/a/b/c"),
+            ("/abc/", "/abc/"),
+            ("/a/b/c/", "/a/b/c/"),
+            // missing root
+            ("", "/"),
+            ("a/", "/a/"),
+            ("abc", "/abc"),
+            ("abc/def", "/abc/def"),
+            ("a/b/c", "/a/b/c"),
+            // remove doubled slash
+            ("//", "/"),
+            ("/abc//", "/abc/"),
+            ("/abc/def//", "/abc/def/"),
+            ("/a/b/c//", "/a/b/c/"),
+            ("/abc//def//ghi", "/abc/def/ghi"),
+            ("//abc", "/abc"),
+            ("///abc", "/abc"),
+            ("//abc//", "/abc/"),
+            // remove . elements
+            (".", "/"),
+            ("./", "/"),
+            ("/abc/./def", "/abc/def"),
+            ("/./abc/def", "/abc/def"),
+            ("/abc/.", "/abc/"),
+            // remove .. elements
+            ("..", "/"),
+            ("../", "/"),
+            ("../../", "/"),
+            ("../..", "/"),
+            ("../../abc", "/abc"),
+            ("/abc/def/ghi/../jkl", "/abc/def/jkl"),
+            ("/abc/def/../ghi/../jkl", "/abc/jkl"),
+            ("/abc/def/..", "/abc"),
+            ("/abc/def/../..", "/"),
+            ("/abc/def/../../..", "/"),
+            ("/abc/def/../../..", "/"),
+            ("/abc/def/../../../ghi/jkl/../../../mno", "/mno"),
+            // combinations
+            ("abc/./../def", "/def"),
+            ("abc//./../def", "/def"),
+            ("abc/../../././../def", "/def"),
+            // a trailing slash is only kept if the path had one
+            ("/abc/def/../", "/abc/"),
+            ("abc/..", "/"),
+            ("/abc/def/./..", "/abc"),
+            // dots within names are left alone
+            ("/abc/.def", "/abc/.def"),
+            ("/abc/..def/", "/abc/..def/"),
+            ("/abc/def..", "/abc/def.."),
+        ];
+
+        for (path, expected) in tests {
+            assert_eq!(clean_path(path), expected, "wrong result for '{}'", path);
+            assert_eq!(
+                clean_path(expected),
+                expected,
+                "'{}' is not clean",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let tests = [
+            ("", Ok("")),
+            ("abc", Ok("abc")),
+            ("John%20Doe", Ok("John Doe")),
+            ("a+b", Ok("a+b")),
+            ("%2F%2f", Ok("//")),
+            ("%E2%9C%93", Ok("✓")),
+            ("100%25", Ok("100%")),
+            ("%", Err(DecodeError::InvalidEscape)),
+            ("%2", Err(DecodeError::InvalidEscape)),
+            ("%zz", Err(DecodeError::InvalidEscape)),
+            ("%E2%9C", Err(DecodeError::InvalidUtf8)),
+            ("%FF", Err(DecodeError::InvalidUtf8)),
+        ];
+
+        for (value, expected) in tests {
+            assert_eq!(
+                percent_decode(value).as_deref().map_err(|&e| e),
+                expected,
+                "wrong result for '{}'",
+                value
+            );
+        }
+
+        assert!(matches!(percent_decode("abc"), Ok(Cow::Borrowed("abc"))));
+    }
+}
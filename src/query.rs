@@ -0,0 +1,133 @@
+use crate::path::percent_decode;
+use crate::DecodeError;
+
+use std::borrow::Cow;
+use std::str;
+
+/// The query string of a request target, returned along with a match by
+/// [`Router::at_uri`](crate::Router::at_uri).
+///
+/// Pairs are separated by `&`, and a key without a `=` has an empty value. Keys and values
+/// are returned as they appear in the query, without any decoding.
+///
+/// ```rust
+/// # use matchit::Router;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut router = Router::new();
+/// router.insert("/search", true)?;
+///
+/// let matched = router.at_uri("/search?q=rust+router&page=2&safe")?;
+/// assert_eq!(matched.query.get("page"), Some("2"));
+/// assert_eq!(matched.query.get("safe"), Some(""));
+/// assert_eq!(matched.query.get_decoded("q").unwrap()?, "rust router");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Eq, Default, Copy, Clone)]
+pub struct Query<'v> {
+    query: &'v str,
+}
+
+impl<'v> Query<'v> {
+    pub(crate) fn new(query: &'v str) -> Self {
+        Self { query }
+    }
+
+    /// Returns the query string, without the leading `?`.
+    pub fn as_str(&self) -> &'v str {
+        self.query
+    }
+
+    /// Returns the value of the first pair with the given key.
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&'v str> {
+        let key = key.as_ref();
+        self.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    /// Returns the value of the first pair with the given key, with any `+` decoded to a space
+    /// and any percent-escapes decoded.
+    pub fn get_decoded(&self, key: impl AsRef<str>) -> Option<Result<Cow<'v, str>, DecodeError>> {
+        self.get(key).map(|value| {
+            if !value.contains('+') {
+                return percent_decode(value);
+            }
+
+            let value = value.replace('+', " ");
+            percent_decode(&value).map(|decoded| Cow::Owned(decoded.into_owned()))
+        })
+    }
+
+    /// Returns an iterator over the keys and values of the query, in order.
+    pub fn iter(&self) -> QueryIter<'v> {
+        QueryIter {
+            pairs: self.query.split('&'),
+        }
+    }
+
+    /// Returns `true` if the query has no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// An iterator over the keys and values of a [`Query`].
+pub struct QueryIter<'v> {
+    pairs: str::Split<'v, char>,
+}
+
+impl<'v> Iterator for QueryIter<'v> {
+    type Item = (&'v str, &'v str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pairs
+            .by_ref()
+            .find(|pair| !pair.is_empty())
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+    }
+}
+
+// Splits a request target into its path and query, dropping any fragment.
+pub(crate) fn split_uri(uri: &str) -> (&str, &str) {
+    let uri = uri.split('#').next().unwrap_or(uri);
+    uri.split_once('?').unwrap_or((uri, ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs() {
+        let query = Query::new("a=1&&b=&c&a=2&d=x=y");
+        assert!(query
+            .iter()
+            .eq([("a", "1"), ("b", ""), ("c", ""), ("a", "2"), ("d", "x=y")]));
+        assert_eq!(query.get("a"), Some("1"));
+        assert_eq!(query.get("e"), None);
+
+        assert!(Query::new("").is_empty());
+        assert!(Query::new("&&").is_empty());
+    }
+
+    #[test]
+    fn decoded() {
+        let query = Query::new("q=a+b%2Bc&bad=%2&plain=x");
+        assert_eq!(query.get_decoded("q"), Some(Ok("a b+c".into())));
+        assert_eq!(
+            query.get_decoded("bad"),
+            Some(Err(DecodeError::InvalidEscape))
+        );
+        assert!(matches!(
+            query.get_decoded("plain"),
+            Some(Ok(Cow::Borrowed("x")))
+        ));
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(split_uri("/a?b=c#d"), ("/a", "b=c"));
+        assert_eq!(split_uri("/a#d?e"), ("/a", ""));
+        assert_eq!(split_uri("/a?"), ("/a", ""));
+        assert_eq!(split_uri("/a"), ("/a", ""));
+    }
+}
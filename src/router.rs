@@ -1,19 +1,50 @@
-use crate::tree::Node;
-use crate::{InsertError, MatchError, Params};
+use crate::query::split_uri;
+use crate::tree::{
+    expand_optional, find_wildcard, split_constraint, unescape, Constraint, Constraints, Node,
+};
+use crate::{
+    clean_path, FrozenError, FrozenRouter, InsertError, MatchError, MergeError, Params, Query,
+    UrlError,
+};
+
+use std::collections::HashMap;
+use std::{mem, slice, vec};
 
 /// A URL router.
 ///
 /// See [the crate documentation](crate) for details.
+///
+/// With the `serde` feature, a router is serialized as a list of its routes and values, and
+/// deserialized by inserting them again. Route names are not part of the list, and constraints
+/// cannot be serialized, so a router with constrained routes must be deserialized into a router
+/// that has them registered through its `DeserializeSeed` implementation.
 #[derive(Clone)]
 #[cfg_attr(test, derive(Debug))]
 pub struct Router<T> {
-    root: Node<T>,
+    // maps every route in the tree to its entry, a route with optional segments
+    // is expanded into multiple routes that share an entry
+    root: Node<usize>,
+    entries: Vec<Entry<T>>,
+    // route names, mapped to their original route
+    names: HashMap<String, String>,
+    constraints: Constraints,
+}
+
+/// A route as it was inserted, along with its value.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug))]
+struct Entry<T> {
+    route: String,
+    value: T,
 }
 
 impl<T> Default for Router<T> {
     fn default() -> Self {
         Self {
             root: Node::default(),
+            entries: Vec::new(),
+            names: HashMap::new(),
+            constraints: Constraints::new(),
         }
     }
 }
@@ -26,6 +57,11 @@ impl<T> Router<T> {
 
     /// Insert a route.
     ///
+    /// Trailing parameters followed by a `?`, like `/posts/:page?`, are optional. The
+    /// route then also matches without them, and all of its forms share the same value.
+    /// An optional catch-all parameter, like `/static/*path?`, also matches the bare
+    /// prefix, `/static/`, with an empty value.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -34,11 +70,395 @@ impl<T> Router<T> {
     /// let mut router = Router::new();
     /// router.insert("/home", "Welcome!")?;
     /// router.insert("/users/:id", "A User")?;
+    /// router.insert("/posts/:year?/:month?", "Posts")?;
+    ///
+    /// assert_eq!(*router.at("/posts")?.value, "Posts");
+    /// assert_eq!(router.at("/posts/2022/12")?.params.get("month"), Some("12"));
+    ///
+    /// router.insert("/static/*path?", "Static")?;
+    /// assert_eq!(router.at("/static/")?.params.get("path"), Some(""));
     /// # Ok(())
     /// # }
     /// ```
     pub fn insert(&mut self, route: impl Into<String>, value: T) -> Result<(), InsertError> {
-        self.root.insert(route, value)
+        self.insert_entry(route.into(), value)
+    }
+
+    // inserts every expansion of the route, or none of them
+    fn insert_entry(&mut self, route: String, value: T) -> Result<(), InsertError> {
+        let routes = expand_optional(&route)?;
+
+        // a single insertion leaves the tree unchanged if it fails, but every expansion
+        // must be checked before inserting any of them
+        if routes.len() > 1 {
+            let mut skeleton = self.skeleton();
+            check_route(&mut skeleton, &route, &self.constraints)?;
+        }
+
+        let i = self.entries.len();
+        let entries = &self.entries;
+        let route_of = |&i: &usize| entries[i].route.clone();
+
+        for expanded in routes {
+            if let Err(err) = self.root.insert(expanded, i, &self.constraints, &route_of) {
+                return Err(original_route(err, &route));
+            }
+        }
+
+        self.entries.push(Entry { route, value });
+        Ok(())
+    }
+
+    // returns a copy of the tree holding the route of each value, to check insertions against
+    fn skeleton(&self) -> Node<String> {
+        self.root.skeleton(&|&i| self.entries[i].route.clone())
+    }
+
+    // returns the index of the entry for a route, in the same form it was inserted in
+    fn find_entry(&self, route: &str) -> Option<usize> {
+        let routes = expand_optional(route).ok()?;
+
+        // SAFETY: We only expose &mut T through &mut self
+        let i = unsafe { *self.root.get(routes[0].as_str())?.get() };
+        if self.entries[i].route != route {
+            return None;
+        }
+
+        Some(i)
+    }
+
+    // returns the value of a route, in the same form it was inserted in
+    pub(crate) fn route_mut(&mut self, route: &str) -> Option<&mut T> {
+        let i = self.find_entry(route)?;
+        Some(&mut self.entries[i].value)
+    }
+
+    /// Register a named constraint for route parameters.
+    ///
+    /// A parameter followed by the name of a constraint in angle brackets, like `/:id<num>`,
+    /// only matches values that the constraint accepts. If it rejects a value, the router
+    /// keeps searching for other matching routes. Constraints must be registered before the
+    /// routes that use them, and redefining a constraint only affects routes inserted afterwards.
+    ///
+    /// Only one named parameter can take up a given position, so routes can't use different
+    /// constraints there: `/users/:id<num>` and `/users/:slug<alpha>` conflict with
+    /// [`ConflictKind::Constraint`](crate::ConflictKind::Constraint). A rejected value falls
+    /// back to static segments and to routes that branch off earlier, like `/:page/me` below,
+    /// but never to another parameter at the same position. To accept either kind of value,
+    /// register a single parameter with a constraint that accepts both.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::{ConflictKind, InsertError, Router};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    /// router.constraint("alpha", |id| id.bytes().all(|c| c.is_ascii_alphabetic()));
+    /// router.insert("/users/:id<num>", "A User")?;
+    /// router.insert("/:page/me", "Me")?;
+    ///
+    /// let matched = router.at("/users/978")?;
+    /// assert_eq!(matched.params.get("id"), Some("978"));
+    /// assert_eq!(*matched.value, "A User");
+    ///
+    /// assert_eq!(*router.at("/users/me")?.value, "Me");
+    ///
+    /// // a parameter with another constraint can't share the position
+    /// assert!(matches!(
+    ///     router.insert("/users/:slug<alpha>", "A Slug"),
+    ///     Err(InsertError::Conflict { kind: ConflictKind::Constraint, .. })
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn constraint(
+        &mut self,
+        name: impl Into<String>,
+        constraint: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        self.constraints
+            .insert(name.into(), Constraint::new(constraint));
+    }
+
+    /// Insert a route under the given name.
+    ///
+    /// Named routes can be turned back into URLs with [`url_for`](Router::url_for).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert_named("user", "/users/:id", "A User")?;
+    ///
+    /// assert_eq!(router.url_for("user", [("id", "978")])?, "/users/978");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_named(
+        &mut self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        value: T,
+    ) -> Result<(), InsertError> {
+        let name = name.into();
+        if self.names.contains_key(&name) {
+            return Err(InsertError::DuplicateName { name });
+        }
+
+        let route = route.into();
+        self.insert_entry(route.clone(), value)?;
+        self.names.insert(name, route);
+        Ok(())
+    }
+
+    /// Insert every route of another router under the given prefix.
+    ///
+    /// The prefix and each route are joined by exactly one `/`, so `/api` or `/api/`
+    /// and a nested `/users` route make up `/api/users`, and a nested `/` route makes
+    /// up `/api/`. If any of the routes conflict, the first conflict
+    /// is returned and the router is left unchanged. Constraints of the nested router
+    /// are carried over, unless a constraint with the same name is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut api = Router::new();
+    /// api.insert("/users/:id", "A User")?;
+    ///
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    /// router.nest("/api/v1", api)?;
+    ///
+    /// let matched = router.at("/api/v1/users/978")?;
+    /// assert_eq!(matched.params.get("id"), Some("978"));
+    /// assert_eq!(*matched.value, "A User");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nest(&mut self, prefix: &str, mut router: Router<T>) -> Result<(), InsertError> {
+        let names = mem::take(&mut router.names);
+        if let Some(name) = names.keys().find(|&name| self.names.contains_key(name)) {
+            return Err(InsertError::DuplicateName { name: name.clone() });
+        }
+
+        let mut constraints = self.constraints.clone();
+        for (name, constraint) in mem::take(&mut router.constraints) {
+            constraints.entry(name).or_insert(constraint);
+        }
+
+        let routes = router
+            .into_iter()
+            .map(|(route, value)| (nested_route(prefix, &route), value))
+            .collect::<Vec<_>>();
+
+        // make sure every route can be inserted before touching the router
+        let mut skeleton = self.skeleton();
+        for (route, _) in &routes {
+            check_route(&mut skeleton, route, &constraints)?;
+        }
+
+        self.constraints = constraints;
+        for (route, value) in routes {
+            self.insert_entry(route, value)?;
+        }
+
+        self.names.extend(
+            names
+                .into_iter()
+                .map(|(name, route)| (name, nested_route(prefix, &route))),
+        );
+
+        Ok(())
+    }
+
+    /// Move every route of another router into this one.
+    ///
+    /// Unlike [`insert`](Router::insert), merging does not stop at the first error.
+    /// Every route that could not be inserted is reported in the returned
+    /// [`MergeError`] along with its error, and its value is dropped. The remaining
+    /// routes are merged regardless. Constraints of the other router are carried over,
+    /// unless a constraint with the same name is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::{ConflictKind, InsertError, Router};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    ///
+    /// let mut other = Router::new();
+    /// other.insert("/home", "Welcome back!")?;
+    /// other.insert("/users/:id", "A User")?;
+    ///
+    /// let errors = router.merge(other).unwrap_err();
+    /// assert_eq!(
+    ///     errors[..],
+    ///     [(
+    ///         "/home".to_owned(),
+    ///         InsertError::Conflict {
+    ///             route: "/home".into(),
+    ///             with: "/home".into(),
+    ///             segment: "home".into(),
+    ///             kind: ConflictKind::Duplicate,
+    ///         }
+    ///     )]
+    /// );
+    ///
+    /// assert_eq!(*router.at("/home")?.value, "Welcome!");
+    /// assert_eq!(*router.at("/users/978")?.value, "A User");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&mut self, mut other: Router<T>) -> Result<(), MergeError> {
+        let mut errors = Vec::new();
+
+        for (name, constraint) in mem::take(&mut other.constraints) {
+            self.constraints.entry(name).or_insert(constraint);
+        }
+
+        // names are looked up by route
+        let mut names = other
+            .names
+            .drain()
+            .map(|(name, route)| (route, name))
+            .collect::<HashMap<_, _>>();
+
+        for (route, value) in other {
+            let name = names.remove(&route);
+            if let Some(name) = name.as_ref().filter(|&name| self.names.contains_key(name)) {
+                let name = name.clone();
+                errors.push((route, InsertError::DuplicateName { name }));
+                continue;
+            }
+
+            // a failed insertion leaves the tree unchanged
+            match self.insert_entry(route.clone(), value) {
+                Ok(()) => {
+                    if let Some(name) = name {
+                        self.names.insert(name, route);
+                    }
+                }
+                Err(err) => errors.push((route, err)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(MergeError(errors))
+        }
+    }
+
+    /// Returns the path a client should be redirected to, if the given path does not
+    /// match any route as is but a corrected version of it does.
+    ///
+    /// The path is first cleaned with [`clean_path`], and then checked for a missing or
+    /// extra trailing slash. `None` is returned if the path already matches a route, or
+    /// if no route matches even after correcting it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/users/:id/", "A User")?;
+    ///
+    /// assert_eq!(router.fix_path("/users/978").as_deref(), Some("/users/978/"));
+    /// assert_eq!(router.fix_path("//users/./978/").as_deref(), Some("/users/978/"));
+    /// assert_eq!(router.fix_path("/posts/../users/978").as_deref(), Some("/users/978/"));
+    /// assert_eq!(router.fix_path("/users/978/"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fix_path(&self, path: &str) -> Option<String> {
+        let cleaned = clean_path(path);
+
+        let fixed = match self.at(&cleaned) {
+            Ok(_) if cleaned == path => return None,
+            Ok(_) => return Some(cleaned),
+            Err(err) => err.redirect_path(&cleaned)?,
+        };
+
+        // trailing slash hints are best-effort
+        if self.at(&fixed).is_err() {
+            return None;
+        }
+
+        Some(fixed)
+    }
+
+    /// Makes a case-insensitive lookup of the given path, returning the path in the
+    /// casing of the route it matched. Parameter values are kept as is.
+    ///
+    /// If `fix_trailing_slash` is true, a path with a missing or extra trailing slash is
+    /// corrected as well. This can be used to redirect users to the canonical URL of a
+    /// route.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/Users/:id/", "A User")?;
+    ///
+    /// assert_eq!(router.path_ignore_case("/users/Ferris/", false).as_deref(), Some("/Users/Ferris/"));
+    /// assert_eq!(router.path_ignore_case("/USERS/Ferris", false), None);
+    /// assert_eq!(router.path_ignore_case("/USERS/Ferris", true).as_deref(), Some("/Users/Ferris/"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn path_ignore_case(&self, path: &str, fix_trailing_slash: bool) -> Option<String> {
+        self.root.path_ignore_case(path, fix_trailing_slash)
+    }
+
+    /// Remove a given route from the router.
+    ///
+    /// Returns the value stored under the route if it was found. The route must be
+    /// provided exactly as it was inserted, including any parameter names and
+    /// optional segments.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    ///
+    /// assert_eq!(router.remove("/home"), Some("Welcome!"));
+    /// assert_eq!(router.remove("/home"), None);
+    /// assert!(router.at("/home").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, route: impl Into<String>) -> Option<T> {
+        let route = route.into();
+        let i = self.find_entry(&route)?;
+
+        for expanded in expand_optional(&route).unwrap() {
+            self.root.remove(expanded);
+        }
+
+        let entry = self.entries.swap_remove(i);
+
+        // point the routes of the entry that took its place to the new index
+        if let Some(moved) = self.entries.get(i) {
+            for expanded in expand_optional(&moved.route).unwrap() {
+                // SAFETY: We have &mut self
+                unsafe { *self.root.get(expanded).unwrap().get() = i };
+            }
+        }
+
+        self.names.retain(|_, named| *named != route);
+        Some(entry.value)
     }
 
     /// Tries to find a value in the router matching the given path.
@@ -57,12 +477,31 @@ impl<T> Router<T> {
     /// # }
     /// ```
     pub fn at<'m, 'p>(&'m self, path: &'p str) -> Result<Match<'m, 'p, &'m T>, MatchError> {
-        match self.root.at(path.as_bytes()) {
-            Ok((value, params)) => Ok(Match {
+        self.at_where(path, |_| true)
+    }
+
+    // finds the value for a path like `at`, skipping over the values rejected by `accept`
+    pub(crate) fn at_where<'m, 'p>(
+        &'m self,
+        path: &'p str,
+        mut accept: impl FnMut(&'m T) -> bool,
+    ) -> Result<Match<'m, 'p, &'m T>, MatchError> {
+        let entries = &self.entries;
+        match self
+            .root
+            .at_where(path.as_bytes(), |&i| accept(&entries[i].value))
+        {
+            Ok((i, mut params)) => {
                 // SAFETY: We only expose &mut T through &mut self
-                value: unsafe { &*value.get() },
-                params,
-            }),
+                let Entry { route, value } = &self.entries[unsafe { *i.get() }];
+                fill_optional(route, &mut params);
+                Ok(Match {
+                    value,
+                    params,
+                    query: Query::default(),
+                    route,
+                })
+            }
             Err(e) => Err(e),
         }
     }
@@ -87,28 +526,500 @@ impl<T> Router<T> {
         &'m mut self,
         path: &'p str,
     ) -> Result<Match<'m, 'p, &'m mut T>, MatchError> {
-        match self.root.at(path.as_bytes()) {
-            Ok((value, params)) => Ok(Match {
+        self.at_mut_where(path, |_| true)
+    }
+
+    // finds the value for a path like `at_mut`, skipping over the values rejected by `accept`
+    pub(crate) fn at_mut_where<'m, 'p>(
+        &'m mut self,
+        path: &'p str,
+        mut accept: impl FnMut(&T) -> bool,
+    ) -> Result<Match<'m, 'p, &'m mut T>, MatchError> {
+        let entries = &self.entries;
+        match self
+            .root
+            .at_where(path.as_bytes(), |&i| accept(&entries[i].value))
+        {
+            Ok((i, mut params)) => {
                 // SAFETY: We have &mut self
-                value: unsafe { &mut *value.get() },
-                params,
-            }),
+                let Entry { route, value } = &mut self.entries[unsafe { *i.get() }];
+                fill_optional(route, &mut params);
+                Ok(Match {
+                    value,
+                    params,
+                    query: Query::default(),
+                    route,
+                })
+            }
             Err(e) => Err(e),
         }
     }
 
+    /// Tries to find a value in the router matching the path of a request target, like
+    /// `/search?q=rust#results`.
+    ///
+    /// The query and fragment are stripped before the path is matched, and the query is
+    /// returned along with the match.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/users/:id", "A User")?;
+    ///
+    /// let matched = router.at_uri("/users/978?fields=name")?;
+    /// assert_eq!(matched.params.get("id"), Some("978"));
+    /// assert_eq!(matched.query.get("fields"), Some("name"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at_uri<'m, 'p>(&'m self, uri: &'p str) -> Result<Match<'m, 'p, &'m T>, MatchError> {
+        let (path, query) = split_uri(uri);
+        let mut matched = self.at(path)?;
+        matched.query = Query::new(query);
+        Ok(matched)
+    }
+
+    /// Builds a URL for a named route, filling in its parameters.
+    ///
+    /// Every parameter of the route must be provided, and no others. Optional parameters
+    /// may be left out, along with any that follow them. The values are inserted as is,
+    /// without any percent-encoding, and must satisfy the constraints of their parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert_named("file", "/:user/files/*path", "A File")?;
+    ///
+    /// let url = router.url_for("file", [("user", "ferris"), ("path", "src/lib.rs")])?;
+    /// assert_eq!(url, "/ferris/files/src/lib.rs");
+    ///
+    /// // parameters can be reused from a match
+    /// let matched = router.at(&url)?;
+    /// assert_eq!(router.url_for("file", matched.params.iter())?, url);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn url_for<K, V>(
+        &self,
+        name: &str,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<String, UrlError>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let route = self.names.get(name).ok_or(UrlError::UnknownRoute)?;
+        let mut params = params.into_iter().map(Some).collect::<Vec<_>>();
+
+        let mut url = Vec::with_capacity(route.len());
+        let mut rest = route.as_bytes();
+
+        // the route was validated when it was inserted
+        while let Some((wildcard, i)) = find_wildcard(rest).unwrap() {
+            unescape(&rest[..i], &mut url);
+
+            let (wildcard, optional) = match wildcard.strip_suffix(b"?") {
+                // an optional catch-all
+                Some(wildcard) => (wildcard, true),
+                None => (wildcard, rest.get(i + wildcard.len()) == Some(&b'?')),
+            };
+
+            let (key, constraint) = split_constraint(wildcard).unwrap();
+            let key = std::str::from_utf8(&key[1..]).unwrap();
+
+            let value = params
+                .iter_mut()
+                .find(|param| matches!(param, Some((k, _)) if k.as_ref() == key))
+                .and_then(Option::take);
+
+            let value = match value {
+                Some((_, value)) => value,
+                // leave out the optional segment, and any after it
+                None if optional => {
+                    // an optional catch-all keeps the trailing slash
+                    if wildcard[0] == b':' {
+                        url.pop();
+                    }
+
+                    if url.is_empty() {
+                        url.push(b'/');
+                    }
+
+                    rest = b"";
+                    break;
+                }
+                None => {
+                    return Err(UrlError::MissingParam {
+                        name: key.to_owned(),
+                    })
+                }
+            };
+
+            let value = value.as_ref();
+            let constraint = constraint
+                .and_then(|name| self.constraints.get(std::str::from_utf8(name).unwrap()));
+
+            if value.is_empty()
+                || (wildcard[0] == b':' && value.contains('/'))
+                || matches!(constraint, Some(constraint) if !constraint.matches(value))
+            {
+                return Err(UrlError::InvalidParam {
+                    name: key.to_owned(),
+                });
+            }
+
+            url.extend_from_slice(value.as_bytes());
+            rest = &rest[i + wildcard.len() + usize::from(optional)..];
+        }
+
+        unescape(rest, &mut url);
+
+        if let Some((key, _)) = params.into_iter().flatten().next() {
+            return Err(UrlError::ExtraParam {
+                name: key.as_ref().to_owned(),
+            });
+        }
+
+        Ok(String::from_utf8(url).unwrap())
+    }
+
+    /// Returns an iterator over the routes in the router and their values.
+    ///
+    /// Routes are yielded in their original form, as they were inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    /// router.insert("/users/:id", "A User")?;
+    ///
+    /// let mut routes = router.iter().collect::<Vec<_>>();
+    /// routes.sort();
+    ///
+    /// assert_eq!(
+    ///     routes,
+    ///     [("/home".to_owned(), &"Welcome!"), ("/users/:id".to_owned(), &"A User")]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: self.entries.iter(),
+        }
+    }
+
+    /// Returns an iterator over the routes in the router, with mutable references
+    /// to their values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/", 1)?;
+    /// router.insert("/users/:id", 2)?;
+    ///
+    /// for (_, value) in router.iter_mut() {
+    ///     *value *= 10;
+    /// }
+    ///
+    /// assert_eq!(*router.at("/users/1")?.value, 20);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.iter_mut(),
+        }
+    }
+
+    /// Flattens the router into a [`FrozenRouter`], which can be stored as bytes and loaded
+    /// back without rebuilding the tree.
+    ///
+    /// The value of a match is replaced by the index of the route in [`iter`](Router::iter).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/home", "Welcome!")?;
+    /// router.insert("/users/:id", "A User")?;
+    ///
+    /// let frozen = router.freeze()?;
+    /// let matched = frozen.at("/users/978")?;
+    /// assert_eq!(matched.params.get("id"), Some("978"));
+    /// assert_eq!(router.iter().nth(matched.value).unwrap().1, &"A User");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns [`FrozenError::TooLarge`] if the routes of the router take up more than 4 GiB.
+    pub fn freeze(&self) -> Result<FrozenRouter, FrozenError> {
+        FrozenRouter::new(
+            &self.root,
+            self.entries.iter().map(|entry| entry.route.as_str()),
+        )
+    }
+
     #[cfg(feature = "__test_helpers")]
     pub fn check_priorities(&self) -> Result<u32, (u32, u32)> {
         self.root.check_priorities()
     }
 }
 
+// Checks that a route can be inserted, by inserting it into a copy of the tree.
+fn check_route(
+    skeleton: &mut Node<String>,
+    route: &str,
+    constraints: &Constraints,
+) -> Result<(), InsertError> {
+    for expanded in expand_optional(route)? {
+        skeleton
+            .insert(expanded, route.to_owned(), constraints, &String::clone)
+            .map_err(|err| original_route(err, route))?;
+    }
+
+    Ok(())
+}
+
+// Joins a prefix and a nested route by exactly one `/`.
+fn nested_route(prefix: &str, route: &str) -> String {
+    format!(
+        "{}/{}",
+        prefix.trim_end_matches('/'),
+        route.trim_start_matches('/')
+    )
+}
+
+// Reports a conflict of the route as it was inserted, rather than one of its expansions.
+fn original_route(err: InsertError, route: &str) -> InsertError {
+    match err {
+        InsertError::Conflict {
+            with,
+            segment,
+            kind,
+            ..
+        } => InsertError::Conflict {
+            route: route.to_owned(),
+            with,
+            segment,
+            kind,
+        },
+        err => err,
+    }
+}
+
+// Fills in the empty value of an optional catch-all parameter that was matched by
+// the bare prefix of its route, ex: `/static/` for `/static/*path?`.
+pub(crate) fn fill_optional<'m>(route: &'m str, params: &mut Params<'m, '_>) {
+    let route = match route.strip_suffix('?') {
+        Some(route) => route,
+        None => return,
+    };
+
+    let name = match route.rfind("/*") {
+        Some(i) => &route[i + 2..],
+        None => return,
+    };
+
+    // the route ends with an optional named parameter
+    if name.contains('/') {
+        return;
+    }
+
+    if params.get(name).is_none() {
+        params.push(name.as_bytes(), b"");
+    }
+}
+
 /// A successful match consisting of the registered value
 /// and URL parameters, returned by [`Router::at`](Router::at).
+///
+/// Matches returned by [`Router::at_uri`](Router::at_uri) also hold the query of the request target.
 #[derive(Debug)]
 pub struct Match<'k, 'v, V> {
     /// The value stored under the matched node.
     pub value: V,
     /// The route parameters. See [parameters](crate#parameters) for more details.
     pub params: Params<'k, 'v>,
+    /// The query string, which is empty unless the match was returned by
+    /// [`Router::at_uri`](Router::at_uri).
+    pub query: Query<'v>,
+    pub(crate) route: &'k str,
+}
+
+impl<'k, 'v, V> Match<'k, 'v, V> {
+    /// Returns the matched route, in the same form it was inserted in.
+    ///
+    /// ```rust
+    /// # use matchit::Router;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut router = Router::new();
+    /// router.insert("/users/:id/posts/:page?", true)?;
+    ///
+    /// assert_eq!(router.at("/users/1/posts")?.route(), "/users/:id/posts/:page?");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn route(&self) -> &'k str {
+        self.route
+    }
+}
+
+impl<'m, T> IntoIterator for &'m Router<T> {
+    type Item = (String, &'m T);
+    type IntoIter = Iter<'m, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'m, T> IntoIterator for &'m mut Router<T> {
+    type Item = (String, &'m mut T);
+    type IntoIter = IterMut<'m, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> IntoIterator for Router<T> {
+    type Item = (String, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            entries: self.entries.into_iter(),
+        }
+    }
+}
+
+/// An iterator over the routes and values of a [`Router`], returned by [`Router::iter`].
+pub struct Iter<'m, T> {
+    entries: slice::Iter<'m, Entry<T>>,
+}
+
+impl<'m, T> Iterator for Iter<'m, T> {
+    type Item = (String, &'m T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries
+            .next()
+            .map(|entry| (entry.route.clone(), &entry.value))
+    }
+}
+
+/// An iterator over the routes and mutable values of a [`Router`], returned by
+/// [`Router::iter_mut`].
+pub struct IterMut<'m, T> {
+    entries: slice::IterMut<'m, Entry<T>>,
+}
+
+impl<'m, T> Iterator for IterMut<'m, T> {
+    type Item = (String, &'m mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries
+            .next()
+            .map(|entry| (entry.route.clone(), &mut entry.value))
+    }
+}
+
+/// An owning iterator over the routes and values of a [`Router`].
+pub struct IntoIter<T> {
+    entries: vec::IntoIter<Entry<T>>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (String, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|entry| (entry.route, entry.value))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Router<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.entries
+                .iter()
+                .map(|entry| (&entry.route, &entry.value)),
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Router<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut router = Router::new();
+        serde::de::DeserializeSeed::deserialize(&mut router, deserializer)?;
+        Ok(router)
+    }
+}
+
+/// Inserts a serialized list of routes and values into an existing router, which can have
+/// constraints registered.
+///
+/// ```rust
+/// # use matchit::Router;
+/// use serde::de::DeserializeSeed;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut router: Router<String> = Router::new();
+/// router.constraint("num", |id| id.parse::<u64>().is_ok());
+///
+/// let mut json = serde_json::Deserializer::from_str(r#"[["/users/:id<num>", "A User"]]"#);
+/// (&mut router).deserialize(&mut json)?;
+///
+/// assert_eq!(*router.at("/users/978")?.value, "A User");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::de::DeserializeSeed<'de> for &mut Router<T> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(RouterVisitor { router: self })
+    }
+}
+
+// Inserts every route of a sequence, failing at the first one that cannot be inserted.
+#[cfg(feature = "serde")]
+struct RouterVisitor<'m, T> {
+    router: &'m mut Router<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'm, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for RouterVisitor<'m, T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a sequence of routes and values")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some((route, value)) = seq.next_element::<(String, T)>()? {
+            self.router
+                .insert(route, value)
+                .map_err(serde::de::Error::custom)?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,178 @@
+use crate::{MatchError, Router};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use http::{Request, Response, StatusCode};
+use tower_service::Service;
+
+/// A [`Service`] that dispatches requests to the service registered for their path.
+///
+/// The parameters of the matched route are inserted into the extensions of the request
+/// as [`OwnedParams`](crate::OwnedParams). Requests that don't match a route are passed
+/// to the [fallback](RouterService::fallback) service along with the [`MatchError`], or
+/// answered with an empty `404 Not Found` response if there is none.
+///
+/// Each request is handled by a clone of the matched service, which is driven to readiness
+/// before it is called, so the router itself is always ready. As with any other service,
+/// middleware can be applied to the whole router with a [`Layer`](https://docs.rs/tower/latest/tower/trait.Layer.html).
+///
+/// ```rust
+/// use matchit::{OwnedParams, Router, RouterService};
+/// use http::{Request, Response};
+/// use tower::{service_fn, BoxError, ServiceExt};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), BoxError> {
+/// async fn user(req: Request<String>) -> Result<Response<String>, BoxError> {
+///     let params = req.extensions().get::<OwnedParams>().unwrap();
+///     Ok(Response::new(format!("User {}", params.get("id").unwrap())))
+/// }
+///
+/// let mut router = Router::new();
+/// router.insert("/users/:id", service_fn(user))?;
+///
+/// let service = RouterService::new(router);
+/// let request = Request::get("/users/978").body(String::new())?;
+///
+/// let response = service.oneshot(request).await?;
+/// assert_eq!(response.body(), "User 978");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RouterService<S> {
+    router: Router<S>,
+    fallback: Option<S>,
+}
+
+impl<S> RouterService<S> {
+    /// Creates a service that dispatches requests to the services registered in the router.
+    pub fn new(router: Router<S>) -> Self {
+        Self {
+            router,
+            fallback: None,
+        }
+    }
+
+    /// Sets the service that handles requests that don't match any route.
+    ///
+    /// The [`MatchError`] is inserted into the extensions of the request, so the fallback
+    /// can, for example, redirect a path with a missing or extra trailing slash.
+    pub fn fallback(mut self, service: S) -> Self {
+        self.fallback = Some(service);
+        self
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RouterService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S, Request<ReqBody>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // the matched service is driven to readiness by the response future
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let matched = match self.router.at(req.uri().path()) {
+            Ok(matched) => Ok((matched.value.clone(), matched.params.into_owned())),
+            Err(err) => Err(err),
+        };
+
+        let service = match matched {
+            Ok((service, params)) => {
+                req.extensions_mut().insert(params);
+                service
+            }
+            Err(err) => {
+                req.extensions_mut().insert::<MatchError>(err);
+
+                match self.fallback {
+                    Some(ref fallback) => fallback.clone(),
+                    None => return ResponseFuture::not_found(),
+                }
+            }
+        };
+
+        ResponseFuture {
+            state: State::Ready {
+                service,
+                req: Some(req),
+            },
+        }
+    }
+}
+
+/// The response future of a [`RouterService`].
+pub struct ResponseFuture<S, Req>
+where
+    S: Service<Req>,
+{
+    state: State<S, Req>,
+}
+
+enum State<S, Req>
+where
+    S: Service<Req>,
+{
+    // waiting for the service to be ready
+    Ready { service: S, req: Option<Req> },
+    // waiting for the response
+    Call(S::Future),
+    // no route or fallback matched the request
+    NotFound,
+}
+
+impl<S, Req> ResponseFuture<S, Req>
+where
+    S: Service<Req>,
+{
+    fn not_found() -> Self {
+        Self {
+            state: State::NotFound,
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Future for ResponseFuture<S, Request<ReqBody>>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Output = Result<Response<ResBody>, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: The future of the service is never moved out of `State::Call`, it is
+        // only ever dropped in place. Nothing else in the state is structurally pinned.
+        let state = unsafe { &mut self.get_unchecked_mut().state };
+
+        loop {
+            match state {
+                State::Ready { service, req } => {
+                    match service.poll_ready(cx) {
+                        Poll::Ready(Ok(())) => {}
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => return Poll::Pending,
+                    }
+
+                    let req = req.take().expect("polled after completion");
+                    *state = State::Call(service.call(req));
+                }
+                // SAFETY: See above, the future is pinned as long as `self` is.
+                State::Call(future) => return unsafe { Pin::new_unchecked(future) }.poll(cx),
+                State::NotFound => {
+                    let mut response = Response::new(ResBody::default());
+                    *response.status_mut() = StatusCode::NOT_FOUND;
+                    return Poll::Ready(Ok(response));
+                }
+            }
+        }
+    }
+}
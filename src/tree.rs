@@ -2,7 +2,9 @@ use crate::{InsertError, MatchError, Params};
 
 use std::cell::UnsafeCell;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::mem;
+use std::sync::Arc;
 
 /// The types of nodes the tree can hold
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
@@ -24,8 +26,10 @@ pub struct Node<T> {
     priority: u32,
     wild_child: bool,
     indices: Vec<u8>,
-    // see `at` for why an unsafe cell is needed
-    value: Option<UnsafeCell<T>>,
+    // see `at_where` for why an unsafe cell is needed
+    pub(crate) value: Option<UnsafeCell<T>>,
+    // the predicate that the value of a parameter node must satisfy
+    pub(crate) constraint: Option<Constraint>,
     pub(crate) param_remapping: ParamRemapping,
     pub(crate) node_type: NodeType,
     pub(crate) prefix: Vec<u8>,
@@ -36,17 +40,64 @@ pub struct Node<T> {
 unsafe impl<T: Send> Send for Node<T> {}
 unsafe impl<T: Sync> Sync for Node<T> {}
 
+/// A named predicate that restricts the values a route parameter can match, ex: `/:id<num>`.
+#[derive(Clone)]
+pub struct Constraint(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl Constraint {
+    pub(crate) fn new(f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn matches(&self, value: &str) -> bool {
+        (self.0)(value)
+    }
+
+    // returns `true` if both constraints were created by the same registration
+    pub(crate) fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::fmt::Debug for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Constraint")
+    }
+}
+
+/// The constraints registered with a router, by name.
+pub(crate) type Constraints = HashMap<String, Constraint>;
+
 impl<T> Node<T> {
-    pub fn insert(&mut self, route: impl Into<String>, val: T) -> Result<(), InsertError> {
+    /// Inserts a route, reporting conflicts with the existing route that `route_of` returns
+    /// for its value.
+    pub fn insert(
+        &mut self,
+        route: impl Into<String>,
+        val: T,
+        constraints: &Constraints,
+        route_of: &dyn Fn(&T) -> String,
+    ) -> Result<(), InsertError> {
         let route = route.into().into_bytes();
-        let (route, param_remapping) = normalize_params(route)?;
+        let (route, param_remapping) = normalize_params(&route)?;
         let mut prefix = route.as_ref();
 
+        // make sure every constraint exists before modifying the tree
+        let mut rest = prefix;
+        while let Some((wildcard, i)) = find_param(rest) {
+            resolve_constraint(wildcard, constraints)?;
+            rest = &rest[i + wildcard.len()..];
+        }
+
+        // make sure the route can be inserted before modifying the tree, a failed insertion
+        // would otherwise leave behind split nodes and updated priorities
+        self.check_insert(&route, &param_remapping, route_of)?;
+
         self.priority += 1;
 
         // the tree is empty
         if self.prefix.is_empty() && self.children.is_empty() {
-            let last = self.insert_child(prefix, &route, val)?;
+            let last = self.insert_child(prefix, &route, val, constraints)?;
             last.param_remapping = param_remapping;
             self.node_type = NodeType::Root;
             return Ok(());
@@ -87,17 +138,6 @@ impl<T> Node<T> {
 
                 let next = prefix[0];
 
-                // `/` after param
-                if current.node_type == NodeType::Param
-                    && next == b'/'
-                    && current.children.len() == 1
-                {
-                    current = &mut current.children[0];
-                    current.priority += 1;
-
-                    continue 'walk;
-                }
-
                 // find a child that matches the next path byte
                 for mut i in 0..current.indices.len() {
                     // found a match
@@ -109,57 +149,294 @@ impl<T> Node<T> {
                 }
 
                 // not a wildcard and there is no matching child node, create a new one
-                if !matches!(next, b':' | b'*') && current.node_type != NodeType::CatchAll {
+                if !matches!(next, PARAM | CATCH_ALL) && current.node_type != NodeType::CatchAll {
                     current.indices.push(next);
                     let mut child = current.add_child(Node::default());
                     child = current.update_child_priority(child);
 
                     // insert into the new node
-                    let last = current.children[child].insert_child(prefix, &route, val)?;
+                    let last =
+                        current.children[child].insert_child(prefix, &route, val, constraints)?;
                     last.param_remapping = param_remapping;
                     return Ok(());
                 }
 
                 // inserting a wildcard, and this node already has a wildcard child
                 if current.wild_child {
-                    // wildcards are always at the end
+                    // wildcards are always at the end, `check_insert` made sure this one matches
                     current = current.children.last_mut().unwrap();
                     current.priority += 1;
-
-                    // make sure the wildcard matches
-                    if prefix.len() < current.prefix.len()
-                        || current.prefix != prefix[..current.prefix.len()]
-                        // catch-alls cannot have children 
-                        || current.node_type == NodeType::CatchAll
-                        // check for longer wildcard, e.g. :name and :names
-                        || (current.prefix.len() < prefix.len()
-                            && prefix[current.prefix.len()] != b'/')
-                    {
-                        return Err(InsertError::conflict(&route, prefix, current));
-                    }
-
                     continue 'walk;
                 }
 
                 // otherwise, create the wildcard node
-                let last = current.insert_child(prefix, &route, val)?;
+                let last = current.insert_child(prefix, &route, val, constraints)?;
                 last.param_remapping = param_remapping;
                 return Ok(());
             }
 
+            // exact match, `check_insert` made sure this node is empty, add the value to it
+            current.value = Some(UnsafeCell::new(val));
+            current.param_remapping = param_remapping;
+
+            return Ok(());
+        }
+    }
+
+    // follows the same path as `insert` without modifying the tree, returning the error
+    // that inserting the normalized route would fail with
+    fn check_insert(
+        &self,
+        route: &[u8],
+        param_remapping: &ParamRemapping,
+        route_of: &dyn Fn(&T) -> String,
+    ) -> Result<(), InsertError> {
+        // the tree is empty
+        if self.prefix.is_empty() && self.children.is_empty() {
+            return check_child(route, route);
+        }
+
+        let mut current = self;
+        let mut prefix = route;
+
+        loop {
+            // find the longest common prefix
+            let len = min(prefix.len(), current.prefix.len());
+            let common_prefix = (0..len)
+                .find(|&i| prefix[i] != current.prefix[i])
+                .unwrap_or(len);
+
+            // the node would be split, leaving a node without a value or wildcard child
+            if common_prefix < current.prefix.len() {
+                return check_child(&prefix[common_prefix..], route);
+            }
+
+            // the route has a common prefix, search deeper
+            if prefix.len() > common_prefix {
+                prefix = &prefix[common_prefix..];
+                let next = prefix[0];
+
+                if let Some(i) = current.indices.iter().position(|&c| c == next) {
+                    current = &current.children[i];
+                    continue;
+                }
+
+                // a new static child, or the first wildcard child
+                if (!matches!(next, PARAM | CATCH_ALL) && current.node_type != NodeType::CatchAll)
+                    || !current.wild_child
+                {
+                    return check_child(prefix, route);
+                }
+
+                // wildcards are always at the end
+                current = current.children.last().unwrap();
+
+                let wildcard = match find_param(prefix) {
+                    Some((wildcard, _)) => wildcard,
+                    None => unreachable!(),
+                };
+
+                if current.prefix != wildcard || current.node_type == NodeType::CatchAll {
+                    return Err(InsertError::conflict(
+                        route,
+                        prefix,
+                        current,
+                        param_remapping,
+                        route_of,
+                    ));
+                }
+
+                continue;
+            }
+
             // exact match, this node should be empty
             if current.value.is_some() {
-                return Err(InsertError::conflict(&route, prefix, current));
+                return Err(InsertError::conflict(
+                    route,
+                    prefix,
+                    current,
+                    param_remapping,
+                    route_of,
+                ));
             }
 
-            // add the value to current node
-            current.value = Some(UnsafeCell::new(val));
-            current.param_remapping = param_remapping;
-
             return Ok(());
         }
     }
 
+    /// Removes a route from the tree, returning its value if it was registered.
+    ///
+    /// The route must be passed in the same form it was inserted in, parameter names included.
+    pub fn remove(&mut self, route: impl Into<String>) -> Option<T> {
+        let route = route.into().into_bytes();
+        let (route, param_remapping) = normalize_params(&route).ok()?;
+
+        let value = self.remove_route(&route, &param_remapping)?;
+
+        // the tree is empty, reset the root so the next insertion starts from scratch
+        if self.value.is_none() && self.children.is_empty() {
+            *self = Node::default();
+        } else {
+            self.merge_child();
+        }
+
+        Some(value)
+    }
+
+    /// Returns the value stored under the given route.
+    ///
+    /// Like [`remove`](Node::remove), the route must be passed in the same form it was inserted in.
+    pub fn get(&self, route: impl Into<String>) -> Option<&UnsafeCell<T>> {
+        let route = route.into().into_bytes();
+        let (route, param_remapping) = normalize_params(&route).ok()?;
+
+        let mut current = self;
+        let mut rest = route.as_slice();
+
+        loop {
+            rest = rest.strip_prefix(current.prefix.as_slice())?;
+
+            // this is the node holding the value
+            if rest.is_empty() {
+                if current.param_remapping != param_remapping {
+                    return None;
+                }
+
+                return current.value.as_ref();
+            }
+
+            let i = match rest[0] {
+                // wildcards are always at the end
+                PARAM | CATCH_ALL if current.wild_child => current.children.len() - 1,
+                next => current.indices.iter().position(|&c| c == next)?,
+            };
+
+            current = &current.children[i];
+        }
+    }
+
+    // removes the value at `route`, relative to this node, cleaning up any nodes
+    // left empty along the way
+    fn remove_route(&mut self, route: &[u8], param_remapping: &ParamRemapping) -> Option<T> {
+        let rest = route.strip_prefix(self.prefix.as_slice())?;
+
+        // this is the node holding the value
+        if rest.is_empty() {
+            // `/:a` cannot be used to remove `/:b`
+            if self.param_remapping != *param_remapping {
+                return None;
+            }
+
+            let value = self.value.take()?.into_inner();
+            self.param_remapping = ParamRemapping::new();
+            self.priority -= 1;
+            return Some(value);
+        }
+
+        let i = match rest[0] {
+            // wildcards are always at the end
+            PARAM | CATCH_ALL if self.wild_child => self.children.len() - 1,
+            next => self.indices.iter().position(|&c| c == next)?,
+        };
+
+        let value = self.children[i].remove_route(rest, param_remapping)?;
+        self.priority -= 1;
+
+        let child = &mut self.children[i];
+        if child.value.is_none() && child.children.is_empty() {
+            // the child is no longer needed
+            let child = self.children.remove(i);
+            if child.node_type != NodeType::Static {
+                self.wild_child = false;
+            } else {
+                self.indices.remove(i);
+            }
+        } else {
+            child.merge_child();
+            self.demote_child(i);
+        }
+
+        Some(value)
+    }
+
+    // merges a lone static child back into this node, undoing the split made when
+    // the child was inserted
+    fn merge_child(&mut self) {
+        if self.value.is_some()
+            || self.wild_child
+            || self.children.len() != 1
+            || matches!(self.node_type, NodeType::Param | NodeType::CatchAll)
+        {
+            return;
+        }
+
+        let child = self.children.pop().unwrap();
+        self.prefix.extend_from_slice(&child.prefix);
+        self.indices = child.indices;
+        self.wild_child = child.wild_child;
+        self.value = child.value;
+        self.param_remapping = child.param_remapping;
+        self.children = child.children;
+    }
+
+    // moves a child whose priority was decremented back, keeping children ordered by priority
+    fn demote_child(&mut self, i: usize) {
+        // only static children are reordered
+        if self.children[i].node_type != NodeType::Static {
+            return;
+        }
+
+        let priority = self.children[i].priority;
+        let mut updated = i;
+        while updated + 1 < self.indices.len() && self.children[updated + 1].priority > priority {
+            self.children.swap(updated, updated + 1);
+            self.indices.swap(updated, updated + 1);
+            updated += 1;
+        }
+    }
+
+    /// Returns a copy of the structure of this tree, holding the route of each value.
+    ///
+    /// Inserting into the copy reports the same conflicts as the original tree would.
+    pub(crate) fn skeleton(&self, route_of: &dyn Fn(&T) -> String) -> Node<String> {
+        Node {
+            priority: self.priority,
+            wild_child: self.wild_child,
+            indices: self.indices.clone(),
+            // SAFETY: We only expose &mut T through &mut self
+            value: (self.value.as_ref())
+                .map(|value| UnsafeCell::new(route_of(unsafe { &*value.get() }))),
+            constraint: self.constraint.clone(),
+            param_remapping: self.param_remapping.clone(),
+            node_type: self.node_type.clone(),
+            prefix: self.prefix.clone(),
+            children: (self.children.iter())
+                .map(|child| child.skeleton(route_of))
+                .collect(),
+        }
+    }
+
+    // returns the value of the shortest route passing through this node, ties are broken by
+    // the routes themselves so that the choice doesn't depend on the order of the children
+    pub(crate) fn closest_value(&self) -> &T {
+        fn closest<T>(node: &Node<T>) -> (Vec<u8>, &UnsafeCell<T>) {
+            if let Some(ref value) = node.value {
+                return (node.prefix.clone(), value);
+            }
+
+            // nodes without a value always lead to one
+            let (rest, value) = (node.children.iter())
+                .map(closest)
+                .min_by(|(a, _), (b, _)| (a.len(), a).cmp(&(b.len(), b)))
+                .unwrap();
+
+            ([&node.prefix[..], &rest].concat(), value)
+        }
+
+        // SAFETY: We only expose &mut T through &mut self
+        unsafe { &*closest(self).1.get() }
+    }
+
     // add a child node, keeping wildcards at the end
     fn add_child(&mut self, child: Node<T>) -> usize {
         let len = self.children.len();
@@ -208,12 +485,13 @@ impl<T> Node<T> {
         mut prefix: &[u8],
         route: &[u8],
         val: T,
+        constraints: &Constraints,
     ) -> Result<&mut Node<T>, InsertError> {
         let mut current = self;
 
         loop {
             // search for a wildcard segment
-            let (wildcard, wildcard_index) = match find_wildcard(prefix)? {
+            let (wildcard, wildcard_index) = match find_param(prefix) {
                 Some((w, i)) => (w, i),
                 // no wildcard, simply use the current node
                 None => {
@@ -224,7 +502,7 @@ impl<T> Node<T> {
             };
 
             // regular route parameter
-            if wildcard[0] == b':' {
+            if wildcard[0] == PARAM {
                 // insert prefix before the current wildcard
                 if wildcard_index > 0 {
                     current.prefix = prefix[..wildcard_index].to_owned();
@@ -234,6 +512,7 @@ impl<T> Node<T> {
                 let child = Self {
                     node_type: NodeType::Param,
                     prefix: wildcard.to_owned(),
+                    constraint: resolve_constraint(wildcard, constraints)?,
                     ..Self::default()
                 };
 
@@ -243,9 +522,11 @@ impl<T> Node<T> {
                 current.priority += 1;
 
                 // if the route doesn't end with the wildcard, then there
-                // will be another non-wildcard subroute starting with '/'
+                // will be another non-wildcard subroute, starting with '/'
+                // or a suffix within the same segment
                 if wildcard.len() < prefix.len() {
                     prefix = &prefix[wildcard.len()..];
+                    current.indices.push(prefix[0]);
                     let child = Self {
                         priority: 1,
                         ..Self::default()
@@ -261,7 +542,7 @@ impl<T> Node<T> {
                 return Ok(current);
 
             // catch-all route
-            } else if wildcard[0] == b'*' {
+            } else if wildcard[0] == CATCH_ALL {
                 // "/foo/*x/bar"
                 if wildcard_index + wildcard.len() != prefix.len() {
                     return Err(InsertError::InvalidCatchAll);
@@ -295,25 +576,28 @@ impl<T> Node<T> {
     }
 }
 
-struct Skipped<'n, 'p, T> {
+struct Skipped<'p, N> {
     path: &'p [u8],
-    node: &'n Node<T>,
+    node: N,
     params: usize,
+    // for parameter nodes, the shortest value left to try
+    split: usize,
 }
 
 #[rustfmt::skip]
 macro_rules! backtracker {
-    ($skipped_nodes:ident, $path:ident, $current:ident, $params:ident, $backtracking:ident, $walk:lifetime) => {
+    ($skipped_nodes:ident, $path:ident, $current:ident, $params:ident, $backtracking:ident, $split:ident, $walk:lifetime) => {
         macro_rules! try_backtrack {
             () => {
-                // try backtracking to any matching wildcard nodes we skipped while traversing
-                // the tree
+                // try backtracking to any matching wildcard nodes, or longer parameter values,
+                // we skipped while traversing the tree
                 while let Some(skipped) = $skipped_nodes.pop() {
                     if skipped.path.ends_with($path) {
                         $path = skipped.path;
-                        $current = &skipped.node;
+                        $current = skipped.node;
                         $params.truncate(skipped.params);
                         $backtracking = true;
+                        $split = skipped.split;
                         continue $walk;
                     }
                 }
@@ -322,221 +606,515 @@ macro_rules! backtracker {
     };
 }
 
+// Returns the error that `insert_child` would fail with when inserting the rest of a route.
+fn check_child(mut prefix: &[u8], route: &[u8]) -> Result<(), InsertError> {
+    while let Some((wildcard, i)) = find_param(prefix) {
+        if wildcard[0] == PARAM {
+            prefix = &prefix[i + wildcard.len()..];
+            continue;
+        }
+
+        // catch-alls must be at the end of the route, and start with a `/`
+        if i + wildcard.len() != prefix.len() || (prefix == route && route[0] != b'/') {
+            return Err(InsertError::InvalidCatchAll);
+        }
+
+        break;
+    }
+
+    Ok(())
+}
+
+/// Read access to the nodes of a tree.
+///
+/// Implemented by [`Node`] and by the flattened nodes of a [`FrozenRouter`](crate::FrozenRouter),
+/// so that both are matched by [`match_path`].
+pub(crate) trait NodeRef<'n>: Copy {
+    fn prefix(self) -> &'n [u8];
+    fn indices(self) -> &'n [u8];
+    fn child(self, i: usize) -> Self;
+    fn children_len(self) -> usize;
+    fn wild_child(self) -> bool;
+    fn node_type(self) -> NodeType;
+    fn has_value(self) -> bool;
+    // returns `true` if the value satisfies the constraint of this parameter node
+    fn accepts(self, value: &[u8]) -> bool;
+    // the original key of the nth parameter of the route stored at this node
+    fn param_key(self, i: usize) -> Option<&'n [u8]>;
+}
+
+impl<'n, T> NodeRef<'n> for &'n Node<T> {
+    fn prefix(self) -> &'n [u8] {
+        &self.prefix
+    }
+
+    fn indices(self) -> &'n [u8] {
+        &self.indices
+    }
+
+    fn child(self, i: usize) -> Self {
+        &self.children[i]
+    }
+
+    fn children_len(self) -> usize {
+        self.children.len()
+    }
+
+    fn wild_child(self) -> bool {
+        self.wild_child
+    }
+
+    fn node_type(self) -> NodeType {
+        self.node_type.clone()
+    }
+
+    fn has_value(self) -> bool {
+        self.value.is_some()
+    }
+
+    fn accepts(self, value: &[u8]) -> bool {
+        Node::accepts(self, value)
+    }
+
+    fn param_key(self, i: usize) -> Option<&'n [u8]> {
+        self.param_remapping.get(i).map(|key| &key[1..])
+    }
+}
+
 impl<T> Node<T> {
     // it's a bit sad that we have to introduce unsafe here but rust doesn't really have a way
     // to abstract over mutability, so `UnsafeCell` lets us avoid having to duplicate logic between
     // `at` and `at_mut`
-    pub fn at<'n, 'p>(
+    //
+    // values rejected by `accept` are skipped over, as if their routes weren't there
+    pub fn at_where<'n, 'p>(
         &'n self,
         full_path: &'p [u8],
+        mut accept: impl FnMut(&'n T) -> bool,
     ) -> Result<(&'n UnsafeCell<T>, Params<'n, 'p>), MatchError> {
-        let mut current = self;
-        let mut path = full_path;
-        let mut backtracking = false;
-        let mut params = Params::new();
-        let mut skipped_nodes = Vec::new();
+        let (node, params) = match_path(self, full_path, |node: &'n Self| {
+            // SAFETY: values are only mutated through `&mut self`
+            accept(unsafe { &*node.value.as_ref().unwrap().get() })
+        })?;
 
-        'walk: loop {
-            backtracker!(skipped_nodes, path, current, params, backtracking, 'walk);
+        Ok((node.value.as_ref().unwrap(), params))
+    }
+}
 
-            // the path is longer than this node's prefix, we are expecting a child node
-            if path.len() > current.prefix.len() {
-                let (prefix, rest) = path.split_at(current.prefix.len());
+/// Finds the node holding the value for a path, along with the matched parameters.
+///
+/// Nodes whose value is rejected by `accept` are treated as if they had no value, and the
+/// search continues with the other routes that match the path.
+pub(crate) fn match_path<'n, 'p, N: NodeRef<'n>>(
+    root: N,
+    full_path: &'p [u8],
+    mut accept: impl FnMut(N) -> bool,
+) -> Result<(N, Params<'n, 'p>), MatchError> {
+    let mut current = root;
+    let mut path = full_path;
+    let mut backtracking = false;
+    // the shortest value to try for the next parameter
+    let mut split = 1;
+    let mut params = Params::new();
+    let mut skipped_nodes = Vec::new();
+
+    'walk: loop {
+        backtracker!(skipped_nodes, path, current, params, backtracking, split, 'walk);
+
+        // match a route parameter against the next path segment
+        if current.node_type() == NodeType::Param {
+            let end = path.iter().position(|&c| c == b'/').unwrap_or(path.len());
+
+            // try ending the parameter before a static suffix within the segment, ex: `/:name.:ext`,
+            // which needs a child other than `/`
+            let start = mem::replace(&mut split, 1);
+            if !matches!(current.indices(), b"" | b"/") {
+                for i in start..end {
+                    let child = match current.indices().iter().position(|&c| c == path[i]) {
+                        Some(child) => child,
+                        None => continue,
+                    };
 
-                // the prefix matches
-                if prefix == current.prefix {
-                    let first = rest[0];
-                    let consumed = path;
+                    let (param, rest) = path.split_at(i);
+                    if !current.accepts(param) {
+                        continue;
+                    }
+
+                    // come back and try a longer value if the suffix doesn't match
+                    skipped_nodes.push(Skipped {
+                        path,
+                        node: current,
+                        params: params.len(),
+                        split: i + 1,
+                    });
+
+                    // store the parameter value
+                    params.push(&current.prefix()[1..], param);
+
+                    // continue with the child node
                     path = rest;
+                    current = current.child(child);
+                    backtracking = false;
+                    continue 'walk;
+                }
+            }
 
-                    // try searching for a matching static child unless we are currently
-                    // backtracking, which would mean we already traversed them
-                    if !backtracking {
-                        if let Some(i) = current.indices.iter().position(|&c| c == first) {
-                            // keep track of wildcard routes we skipped to backtrack to later if
-                            // we don't find a math
-                            if current.wild_child {
-                                skipped_nodes.push(Skipped {
-                                    path: consumed,
-                                    node: current,
-                                    params: params.len(),
-                                });
-                            }
-
-                            // child won't match because of an extra trailing slash
-                            if path == b"/"
-                                && current.children[i].prefix != b"/"
-                                && current.value.is_some()
-                            {
-                                return Err(MatchError::ExtraTrailingSlash);
-                            }
-
-                            // continue with the child node
-                            current = &current.children[i];
-                            continue 'walk;
-                        }
+            // otherwise, the parameter takes up the entire segment
+            let (param, rest) = path.split_at(end);
+
+            // the parameter value does not satisfy the constraint, try backtracking
+            if !current.accepts(param) {
+                try_backtrack!();
+                return Err(MatchError::NotFound);
+            }
+
+            // this is the last path segment
+            if rest.is_empty() {
+                // store the parameter value
+                params.push(&current.prefix()[1..], param);
+
+                // found the matching value
+                if current.has_value() {
+                    if accept(current) {
+                        // remap parameter keys
+                        params.for_each_key_mut(|(i, key)| remap_key(current, i, key));
+
+                        return Ok((current, params));
                     }
 
-                    // we didn't find a match and there are no children with wildcards, there is no match
-                    if !current.wild_child {
-                        // extra trailing slash
-                        if path == b"/" && current.value.is_some() {
-                            return Err(MatchError::ExtraTrailingSlash);
+                    // the value was rejected, try backtracking
+                    try_backtrack!();
+                    return Err(MatchError::NotFound);
+                }
+
+                // check the child node in case the path is missing a trailing slash
+                if let Some(i) = current.indices().iter().position(|&c| c == b'/') {
+                    let child = current.child(i);
+
+                    if child.prefix() == b"/" && child.has_value() {
+                        return Err(MatchError::MissingTrailingSlash);
+                    }
+                }
+
+                // no match, try backtracking
+                try_backtrack!();
+
+                // this node doesn't have the value, no match
+                return Err(MatchError::NotFound);
+            }
+
+            // there are more segments in the path other than this parameter
+            if let Some(i) = current.indices().iter().position(|&c| c == b'/') {
+                let child = current.child(i);
+
+                // child won't match because of an extra trailing slash
+                if rest == b"/" && child.prefix() != b"/" && current.has_value() {
+                    return Err(MatchError::ExtraTrailingSlash);
+                }
+
+                // store the parameter value
+                params.push(&current.prefix()[1..], param);
+
+                // continue with the child node
+                path = rest;
+                current = child;
+                backtracking = false;
+                continue 'walk;
+            }
+
+            // this node has no children for the next segment...
+            // either the path has an extra trailing slash or there is no match
+            if rest == b"/" && current.has_value() {
+                return Err(MatchError::ExtraTrailingSlash);
+            }
+
+            // try backtracking
+            try_backtrack!();
+
+            return Err(MatchError::NotFound);
+        }
+
+        // the path is longer than this node's prefix, we are expecting a child node
+        if path.len() > current.prefix().len() {
+            let (prefix, rest) = path.split_at(current.prefix().len());
+
+            // the prefix matches
+            if prefix == current.prefix() {
+                let first = rest[0];
+                let consumed = path;
+                path = rest;
+
+                // try searching for a matching static child unless we are currently
+                // backtracking, which would mean we already traversed them
+                if !backtracking {
+                    if let Some(i) = current.indices().iter().position(|&c| c == first) {
+                        // keep track of wildcard routes we skipped to backtrack to later if
+                        // we don't find a math
+                        if current.wild_child() {
+                            skipped_nodes.push(Skipped {
+                                path: consumed,
+                                node: current,
+                                params: params.len(),
+                                split: 1,
+                            });
                         }
 
-                        // try backtracking
-                        if path != b"/" {
-                            try_backtrack!();
+                        // child won't match because of an extra trailing slash
+                        if path == b"/" && current.child(i).prefix() != b"/" && current.has_value()
+                        {
+                            return Err(MatchError::ExtraTrailingSlash);
                         }
 
-                        // nothing found
-                        return Err(MatchError::NotFound);
+                        // continue with the child node
+                        current = current.child(i);
+                        continue 'walk;
+                    }
+                }
+
+                // we didn't find a match and there are no children with wildcards, there is no match
+                if !current.wild_child() {
+                    // extra trailing slash
+                    if path == b"/" && current.has_value() {
+                        return Err(MatchError::ExtraTrailingSlash);
+                    }
+
+                    // try backtracking
+                    if path != b"/" {
+                        try_backtrack!();
                     }
 
-                    // handle the wildcard child, which is always at the end of the list
-                    current = current.children.last().unwrap();
-
-                    match current.node_type {
-                        NodeType::Param => {
-                            // check if there are more segments in the path other than this parameter
-                            match path.iter().position(|&c| c == b'/') {
-                                Some(i) => {
-                                    let (param, rest) = path.split_at(i);
-
-                                    if let [child] = current.children.as_slice() {
-                                        // child won't match because of an extra trailing slash
-                                        if rest == b"/"
-                                            && child.prefix != b"/"
-                                            && current.value.is_some()
-                                        {
-                                            return Err(MatchError::ExtraTrailingSlash);
-                                        }
-
-                                        // store the parameter value
-                                        params.push(&current.prefix[1..], param);
-
-                                        // continue with the child node
-                                        path = rest;
-                                        current = child;
-                                        backtracking = false;
-                                        continue 'walk;
-                                    }
-
-                                    // this node has no children yet the path has more segments...
-                                    // either the path has an extra trailing slash or there is no match
-                                    if path.len() == i + 1 {
-                                        return Err(MatchError::ExtraTrailingSlash);
-                                    }
-
-                                    // try backtracking
-                                    if path != b"/" {
-                                        try_backtrack!();
-                                    }
-
-                                    return Err(MatchError::NotFound);
-                                }
-                                // this is the last path segment
-                                None => {
-                                    // store the parameter value
-                                    params.push(&current.prefix[1..], path);
-
-                                    // found the matching value
-                                    if let Some(ref value) = current.value {
-                                        // remap parameter keys
-                                        params.for_each_key_mut(|(i, key)| {
-                                            *key = &current.param_remapping[i][1..]
-                                        });
-
-                                        return Ok((value, params));
-                                    }
-
-                                    // check the child node in case the path is missing a trailing slash
-                                    if let [child] = current.children.as_slice() {
-                                        current = child;
-
-                                        if (current.prefix == b"/" && current.value.is_some())
-                                            || (current.prefix.is_empty()
-                                                && current.indices == b"/")
-                                        {
-                                            return Err(MatchError::MissingTrailingSlash);
-                                        }
-
-                                        // no match, try backtracking
-                                        if path != b"/" {
-                                            try_backtrack!();
-                                        }
-                                    }
-
-                                    // this node doesn't have the value, no match
-                                    return Err(MatchError::NotFound);
-                                }
-                            }
+                    // nothing found
+                    return Err(MatchError::NotFound);
+                }
+
+                // handle the wildcard child, which is always at the end of the list
+                current = current.child(current.children_len() - 1);
+
+                match current.node_type() {
+                    // parameters are matched by the next iteration
+                    NodeType::Param => continue 'walk,
+                    NodeType::CatchAll => {
+                        // catch all segments are only allowed at the end of the route,
+                        // either this node has the value or there is no match
+                        if !current.has_value() {
+                            return Err(MatchError::NotFound);
                         }
-                        NodeType::CatchAll => {
-                            // catch all segments are only allowed at the end of the route,
-                            // either this node has the value or there is no match
-                            return match current.value {
-                                Some(ref value) => {
-                                    // remap parameter keys
-                                    params.for_each_key_mut(|(i, key)| {
-                                        *key = &current.param_remapping[i][1..]
-                                    });
-
-                                    // store the final catch-all parameter
-                                    params.push(&current.prefix[1..], path);
-
-                                    Ok((value, params))
-                                }
-                                None => Err(MatchError::NotFound),
-                            };
+
+                        // the value was rejected, try backtracking
+                        if !accept(current) {
+                            try_backtrack!();
+                            return Err(MatchError::NotFound);
                         }
-                        _ => unreachable!(),
+
+                        // remap parameter keys
+                        params.for_each_key_mut(|(i, key)| remap_key(current, i, key));
+
+                        // store the final catch-all parameter
+                        params.push(&current.prefix()[1..], path);
+
+                        return Ok((current, params));
                     }
+                    _ => unreachable!(),
                 }
             }
+        }
 
-            // this is it, we should have reached the node containing the value
-            if path == current.prefix {
-                if let Some(ref value) = current.value {
+        // this is it, we should have reached the node containing the value
+        if path == current.prefix() {
+            if current.has_value() {
+                if accept(current) {
                     // remap parameter keys
-                    params.for_each_key_mut(|(i, key)| *key = &current.param_remapping[i][1..]);
-                    return Ok((value, params));
+                    params.for_each_key_mut(|(i, key)| remap_key(current, i, key));
+                    return Ok((current, params));
                 }
 
-                // nope, try backtracking
+                // the value was rejected, try backtracking
                 try_backtrack!();
+                return Err(MatchError::NotFound);
+            }
+
+            // nope, try backtracking
+            try_backtrack!();
+
+            // TODO: does this *always* means there is an extra trailing slash?
+            if path == b"/" && current.wild_child() && current.node_type() != NodeType::Root {
+                return Err(MatchError::unsure(full_path));
+            }
 
-                // TODO: does this *always* means there is an extra trailing slash?
-                if path == b"/" && current.wild_child && current.node_type != NodeType::Root {
-                    return Err(MatchError::unsure(full_path));
+            if !backtracking {
+                // check if the path is missing a trailing slash
+                if let Some(i) = current.indices().iter().position(|&c| c == b'/') {
+                    current = current.child(i);
+
+                    if current.prefix().len() == 1 && current.has_value() {
+                        return Err(MatchError::MissingTrailingSlash);
+                    }
                 }
+            }
 
-                if !backtracking {
-                    // check if the path is missing a trailing slash
-                    if let Some(i) = current.indices.iter().position(|&c| c == b'/') {
-                        current = &current.children[i];
+            return Err(MatchError::NotFound);
+        }
 
-                        if current.prefix.len() == 1 && current.value.is_some() {
-                            return Err(MatchError::MissingTrailingSlash);
-                        }
+        // nothing matches, check for a missing trailing slash
+        if current.prefix().split_last() == Some((&b'/', path)) && current.has_value() {
+            return Err(MatchError::MissingTrailingSlash);
+        }
+
+        // last chance, try backtracking
+        if path != b"/" {
+            try_backtrack!();
+        }
+
+        return Err(MatchError::NotFound);
+    }
+}
+
+// Replaces the normalized key of a parameter with its original key.
+fn remap_key<'n, N: NodeRef<'n>>(node: N, i: usize, key: &mut &'n [u8]) {
+    if let Some(original) = node.param_key(i) {
+        *key = original;
+    }
+}
+
+impl<T> Node<T> {
+    /// Makes a case-insensitive lookup of the given path, returning the path with the
+    /// casing of the route it matched. Parameter values are kept as is.
+    ///
+    /// If `fix_trailing_slash` is true, a path with a missing or extra trailing slash
+    /// is corrected as well.
+    pub fn path_ignore_case(&self, path: &str, fix_trailing_slash: bool) -> Option<String> {
+        let mut insensitive = Vec::with_capacity(path.len() + 1);
+
+        if self.find_ignore_case(0, path, &mut insensitive) {
+            return Some(String::from_utf8(insensitive).unwrap());
+        }
+
+        if !fix_trailing_slash || path.is_empty() {
+            return None;
+        }
+
+        let fixed = match MatchError::unsure(path.as_bytes()) {
+            MatchError::ExtraTrailingSlash => path[..path.len() - 1].to_owned(),
+            _ => format!("{}/", path),
+        };
+
+        insensitive.clear();
+        if self.find_ignore_case(0, &fixed, &mut insensitive) {
+            return Some(String::from_utf8(insensitive).unwrap());
+        }
+
+        None
+    }
+
+    // recursive case-insensitive lookup, starting `offset` bytes into this node's prefix
+    fn find_ignore_case(&self, offset: usize, path: &str, insensitive: &mut Vec<u8>) -> bool {
+        let next = match path.chars().next() {
+            Some(next) => next,
+            None => return offset == self.prefix.len() && self.value.is_some(),
+        };
+
+        let rest = &path[next.len_utf8()..];
+
+        // try every casing of the next character, the prefixes of static nodes might
+        // end in the middle of it
+        let mut cases = [
+            Some(next),
+            single_char(next.to_lowercase()),
+            single_char(next.to_uppercase()),
+        ];
+
+        // don't try the same character twice
+        if cases[1] == cases[0] {
+            cases[1] = None;
+        }
+
+        if cases[2] == cases[0] || cases[2] == cases[1] {
+            cases[2] = None;
+        }
+
+        for c in cases.into_iter().flatten() {
+            let mut buf = [0; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+
+            if let Some((node, offset)) = self.walk_static(offset, bytes) {
+                let len = insensitive.len();
+                insensitive.extend_from_slice(bytes);
+
+                if node.find_ignore_case(offset, rest, insensitive) {
+                    return true;
+                }
+
+                insensitive.truncate(len);
+            }
+        }
+
+        if offset < self.prefix.len() || !self.wild_child {
+            return false;
+        }
+
+        // handle the wildcard child, which is always at the end of the list
+        let child = self.children.last().unwrap();
+        match child.node_type {
+            NodeType::Param => {
+                let end = path.find('/').unwrap_or(path.len());
+
+                // the parameter may be followed by a static suffix within the segment,
+                // so try every possible value
+                for i in (1..=end).filter(|&i| path.is_char_boundary(i)) {
+                    if !child.accepts(&path.as_bytes()[..i]) {
+                        continue;
+                    }
+
+                    let len = insensitive.len();
+                    insensitive.extend_from_slice(&path.as_bytes()[..i]);
+
+                    if child.find_ignore_case(child.prefix.len(), &path[i..], insensitive) {
+                        return true;
                     }
+
+                    insensitive.truncate(len);
                 }
 
-                return Err(MatchError::NotFound);
+                false
+            }
+            NodeType::CatchAll => {
+                insensitive.extend_from_slice(path.as_bytes());
+                child.value.is_some()
             }
+            _ => unreachable!(),
+        }
+    }
+
+    // checks the value of a parameter against the constraint of this node
+    fn accepts(&self, value: &[u8]) -> bool {
+        match self.constraint {
+            // parameter values always end at a `/` or an ASCII suffix, so they remain valid UTF-8
+            Some(ref constraint) => constraint.matches(std::str::from_utf8(value).unwrap()),
+            None => true,
+        }
+    }
+
+    // follows `bytes` through static nodes, starting `offset` bytes into this node's prefix
+    fn walk_static(&self, mut offset: usize, bytes: &[u8]) -> Option<(&Self, usize)> {
+        let mut current = self;
 
-            // nothing matches, check for a missing trailing slash
-            if current.prefix.split_last() == Some((&b'/', path)) && current.value.is_some() {
-                return Err(MatchError::MissingTrailingSlash);
+        for &b in bytes {
+            if offset == current.prefix.len() {
+                current = current
+                    .children
+                    .iter()
+                    .find(|child| child.node_type == NodeType::Static && child.prefix[0] == b)?;
+                offset = 0;
             }
 
-            // last chance, try backtracking
-            if path != b"/" {
-                try_backtrack!();
+            if current.prefix[offset] != b {
+                return None;
             }
 
-            return Err(MatchError::NotFound);
+            offset += 1;
         }
+
+        Some((current, offset))
     }
 
     #[cfg(feature = "__test_helpers")]
@@ -558,108 +1136,338 @@ impl<T> Node<T> {
     }
 }
 
+// returns the character if the iterator yields exactly one
+fn single_char(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
 /// An ordered list of route parameters keys for a specific route, stored at leaf nodes.
-type ParamRemapping = Vec<Vec<u8>>;
+pub(crate) type ParamRemapping = Vec<Vec<u8>>;
+
+/// Marks a route parameter in a normalized route.
+///
+/// Neither marker can appear in a UTF-8 string, so they are never confused with an
+/// escaped `:` or `*` in the static text of a route.
+const PARAM: u8 = 0xFF;
+
+/// Marks a catch-all parameter in a normalized route.
+const CATCH_ALL: u8 = 0xFE;
 
 /// Returns `path` with normalized route parameters, and a parameter remapping
 /// to store at the leaf node for this route.
-fn normalize_params(mut path: Vec<u8>) -> Result<(Vec<u8>, ParamRemapping), InsertError> {
-    let mut start = 0;
+///
+/// Parameters are marked with [`PARAM`] and [`CATCH_ALL`] in the normalized route,
+/// and any escaped `:` or `*` is restored to a single character. Named parameters
+/// are renamed to their position in the route, so a route can hold any number of them.
+fn normalize_params(path: &[u8]) -> Result<(Vec<u8>, ParamRemapping), InsertError> {
+    let mut normalized = Vec::with_capacity(path.len());
     let mut original = ParamRemapping::new();
-
-    // parameter names are normalized alphabetically
-    let mut next = b'a';
+    let mut rest = path;
 
     loop {
-        let (wildcard, mut wildcard_index) = match find_wildcard(&path[start..])? {
+        let (wildcard, wildcard_index) = match find_wildcard(rest)? {
             Some((w, i)) => (w, i),
-            None => return Ok((path, original)),
+            None => {
+                unescape(rest, &mut normalized);
+                return Ok((normalized, original));
+            }
         };
 
+        unescape(&rest[..wildcard_index], &mut normalized);
+        rest = &rest[wildcard_index + wildcard.len()..];
+
+        // the constraint is part of the normalized parameter
+        let (name, _) = split_constraint(wildcard)?;
+
         // makes sure the param has a valid name
-        if wildcard.len() < 2 {
+        if name.len() < 2 {
             return Err(InsertError::UnnamedParam);
         }
 
         // don't need to normalize catch-all parameters
         if wildcard[0] == b'*' {
-            start += wildcard_index + wildcard.len();
+            normalized.push(CATCH_ALL);
+            normalized.extend_from_slice(&wildcard[1..]);
             continue;
         }
 
-        wildcard_index += start;
-
-        // normalize the parameter
-        let removed = path.splice(
-            (wildcard_index)..(wildcard_index + wildcard.len()),
-            vec![b':', next],
-        );
+        // normalize the parameter, digits are valid name characters
+        normalized.push(PARAM);
+        normalized.extend_from_slice(original.len().to_string().as_bytes());
+        normalized.extend_from_slice(&wildcard[name.len()..]);
 
         // remember the original name for remappings
-        original.push(removed.collect());
+        original.push(name.to_owned());
+    }
+}
+
+/// Expands a route with optional trailing parameters into every route it matches.
+///
+/// For example, `/posts/:year?/:month?` is expanded into `/posts`, `/posts/:year`,
+/// and `/posts/:year/:month`. An optional catch-all keeps the trailing slash, so
+/// `/static/*path?` is expanded into `/static/` and `/static/*path`.
+pub(crate) fn expand_optional(route: &str) -> Result<Vec<String>, InsertError> {
+    let mut base = route;
+    let mut optional = Vec::new();
+    let mut catch_all = false;
+
+    // peel off the optional segments, starting from the end
+    while let Some(rest) = base.strip_suffix('?') {
+        // a `?` that doesn't follow a parameter is part of a static segment
+        if !ends_with_wildcard(rest.as_bytes())? {
+            break;
+        }
+
+        let (rest, segment) = match rest.rfind('/') {
+            Some(i) => rest.split_at(i),
+            None => return Err(InsertError::InvalidOptional),
+        };
+
+        // an optional segment must consist of a single parameter
+        match find_wildcard(&segment.as_bytes()[1..])? {
+            Some((wildcard, 0)) if wildcard.len() == segment.len() - 1 => {
+                // an optional catch-all cannot be combined with other optional segments
+                if catch_all || (wildcard[0] == b'*' && !optional.is_empty()) {
+                    return Err(InsertError::InvalidOptional);
+                }
 
-        // get the next key
-        next += 1;
-        if next > b'z' {
-            panic!("too many route parameters");
+                catch_all = wildcard[0] == b'*';
+            }
+            _ => return Err(InsertError::InvalidOptional),
         }
 
-        start = wildcard_index + 2;
+        optional.push(segment);
+        base = rest;
     }
+
+    // the remaining parameters must all be required
+    let mut rest = base.as_bytes();
+    while let Some((wildcard, i)) = find_wildcard(rest)? {
+        rest = &rest[i + wildcard.len()..];
+        if rest.first() == Some(&b'?') {
+            return Err(InsertError::InvalidOptional);
+        }
+    }
+
+    let mut route = base.to_owned();
+    let mut routes = Vec::with_capacity(optional.len() + 1);
+    routes.push(if route.is_empty() || catch_all {
+        format!("{}/", route)
+    } else {
+        route.clone()
+    });
+
+    for segment in optional.into_iter().rev() {
+        route.push_str(segment);
+        routes.push(route.clone());
+    }
+
+    Ok(routes)
+}
+
+// Returns whether the route ends with a parameter.
+fn ends_with_wildcard(route: &[u8]) -> Result<bool, InsertError> {
+    let mut rest = route;
+    while let Some((wildcard, i)) = find_wildcard(rest)? {
+        rest = &rest[i + wildcard.len()..];
+        if rest.is_empty() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
 }
 
 /// Restores `route` to it's original, denormalized form.
 pub(crate) fn denormalize_params(route: &mut Vec<u8>, params: &ParamRemapping) {
-    let mut start = 0;
-    let mut i = 0;
+    let mut denormalized = Vec::with_capacity(route.len());
+    let mut params = params.iter();
+    let mut rest = route.as_slice();
 
-    loop {
-        // find the next wildcard
-        let (wildcard, mut wildcard_index) = match find_wildcard(&route[start..]).unwrap() {
-            Some((w, i)) => (w, i),
-            None => return,
-        };
+    while let Some((wildcard, i)) = find_param(rest) {
+        escape(&rest[..i], &mut denormalized);
+        rest = &rest[i + wildcard.len()..];
+
+        if wildcard[0] == CATCH_ALL {
+            denormalized.push(b'*');
+            denormalized.extend_from_slice(&wildcard[1..]);
+            continue;
+        }
 
-        wildcard_index += start;
+        // only the name of the parameter is normalized
+        let (name, _) = split_constraint(wildcard).unwrap();
 
-        let next = match params.get(i) {
-            Some(param) => param.clone(),
-            None => return,
-        };
+        match params.next() {
+            Some(param) => denormalized.extend_from_slice(param),
+            None => {
+                denormalized.push(b':');
+                denormalized.extend_from_slice(&name[1..]);
+            }
+        }
+
+        denormalized.extend_from_slice(&wildcard[name.len()..]);
+    }
+
+    escape(rest, &mut denormalized);
+    *route = denormalized;
+}
 
-        // denormalize this parameter
-        route.splice(
-            (wildcard_index)..(wildcard_index + wildcard.len()),
-            next.clone(),
-        );
+// Appends the static text of a route, restoring any escaped `:` or `*`.
+pub(crate) fn unescape(text: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < text.len() {
+        out.push(text[i]);
+
+        // the text was validated by `find_wildcard`, so these are always doubled
+        if matches!(text[i], b':' | b'*') {
+            i += 1;
+        }
 
         i += 1;
-        start = wildcard_index + 2;
     }
 }
 
-// Searches for a wildcard segment and checks the path for invalid characters.
-fn find_wildcard(path: &[u8]) -> Result<Option<(&[u8], usize)>, InsertError> {
-    for (start, &c) in path.iter().enumerate() {
-        // a wildcard starts with ':' (param) or '*' (catch-all)
-        if c != b':' && c != b'*' {
-            continue;
+// Appends static text to a route, escaping any `:` or `*`.
+fn escape(text: &[u8], out: &mut Vec<u8>) {
+    for &c in text {
+        if matches!(c, b':' | b'*') {
+            out.push(c);
         }
 
-        for (end, &c) in path[start + 1..].iter().enumerate() {
-            match c {
-                b'/' => return Ok(Some((&path[start..start + 1 + end], start))),
-                b':' | b'*' => return Err(InsertError::TooManyParams),
-                _ => {}
+        out.push(c);
+    }
+}
+
+/// Splits a route parameter into its name and the name of its constraint, if any.
+///
+/// For example, `:id<num>` is split into `:id` and `num`.
+pub(crate) fn split_constraint(wildcard: &[u8]) -> Result<(&[u8], Option<&[u8]>), InsertError> {
+    // catch-all parameters cannot be constrained
+    if !matches!(wildcard[0], b':' | PARAM) {
+        return Ok((wildcard, None));
+    }
+
+    let start = match wildcard.iter().position(|&c| c == b'<') {
+        Some(start) => start,
+        None => return Ok((wildcard, None)),
+    };
+
+    match wildcard[start + 1..].split_last() {
+        Some((b'>', name)) if !name.is_empty() && !name.iter().any(|&c| c == b'<' || c == b'>') => {
+            Ok((&wildcard[..start], Some(name)))
+        }
+        _ => Err(InsertError::InvalidConstraint),
+    }
+}
+
+// Looks up the constraint of a route parameter.
+fn resolve_constraint(
+    wildcard: &[u8],
+    constraints: &Constraints,
+) -> Result<Option<Constraint>, InsertError> {
+    let name = match split_constraint(wildcard)? {
+        (_, Some(name)) => std::str::from_utf8(name).unwrap(),
+        (_, None) => return Ok(None),
+    };
+
+    match constraints.get(name) {
+        Some(constraint) => Ok(Some(constraint.clone())),
+        None => Err(InsertError::UnknownConstraint {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+// Searches for a wildcard segment and checks the path for invalid characters.
+//
+// A `:` or `*` can be used as static text by doubling it, ex: `/v1/projects::batchGet`.
+pub(crate) fn find_wildcard(path: &[u8]) -> Result<Option<(&[u8], usize)>, InsertError> {
+    let mut start = 0;
+
+    while start < path.len() {
+        match path[start] {
+            // an escaped character, skip over it
+            c @ (b':' | b'*') if path.get(start + 1) == Some(&c) => {
+                start += 2;
+                continue;
             }
+            // a route parameter ends at the first character that is not part of its name
+            // or constraint, anything after it is a static suffix
+            b':' => {
+                let mut end = start + 1;
+                while end < path.len() && is_param_char(path[end]) {
+                    end += 1;
+                }
+
+                if path.get(end) == Some(&b'<') {
+                    end += match path[end..].iter().position(|&c| c == b'>' || c == b'/') {
+                        Some(close) if path[end + close] == b'>' => close + 1,
+                        _ => return Err(InsertError::InvalidConstraint),
+                    };
+                }
+
+                // parameters must be separated by static text
+                match path.get(end) {
+                    Some(&c @ (b':' | b'*')) if path.get(end + 1) != Some(&c) => {
+                        return Err(InsertError::TooManyParams)
+                    }
+                    _ => {}
+                }
+
+                return Ok(Some((&path[start..end], start)));
+            }
+            // a catch-all parameter takes up the rest of the segment
+            b'*' => {
+                for (end, &c) in path[start + 1..].iter().enumerate() {
+                    match c {
+                        b'/' => return Ok(Some((&path[start..start + 1 + end], start))),
+                        b':' | b'*' => return Err(InsertError::TooManyParams),
+                        _ => {}
+                    }
+                }
+
+                return Ok(Some((&path[start..], start)));
+            }
+            _ => {}
         }
 
-        return Ok(Some((&path[start..], start)));
+        start += 1;
     }
 
     Ok(None)
 }
 
+// Searches for the next parameter in a normalized route.
+fn find_param(path: &[u8]) -> Option<(&[u8], usize)> {
+    let start = path.iter().position(|&c| c == PARAM || c == CATCH_ALL)?;
+    let mut end = start + 1;
+
+    if path[start] == PARAM {
+        while end < path.len() && is_param_char(path[end]) {
+            end += 1;
+        }
+
+        // the constraint was validated by `find_wildcard`
+        if path.get(end) == Some(&b'<') {
+            end += path[end..].iter().position(|&c| c == b'>').unwrap() + 1;
+        }
+    } else {
+        while end < path.len() && path[end] != b'/' {
+            end += 1;
+        }
+    }
+
+    Some((&path[start..end], start))
+}
+
+// Returns `true` if the character can be part of a parameter name.
+fn is_param_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || !c.is_ascii()
+}
+
 impl<T> Clone for Node<T>
 where
     T: Clone,
@@ -673,6 +1481,7 @@ where
 
         Self {
             value,
+            constraint: self.constraint.clone(),
             prefix: self.prefix.clone(),
             wild_child: self.wild_child,
             node_type: self.node_type.clone(),
@@ -694,6 +1503,7 @@ impl<T> Default for Node<T> {
             indices: Vec::new(),
             children: Vec::new(),
             value: None,
+            constraint: None,
             priority: 0,
         }
     }
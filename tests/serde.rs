@@ -0,0 +1,61 @@
+#![cfg(feature = "serde")]
+
+use matchit::Router;
+use serde::de::DeserializeSeed;
+
+#[test]
+fn roundtrip() {
+    let mut router = Router::new();
+    router.insert("/", 0).unwrap();
+    router.insert("/users/:id", 1).unwrap();
+    router.insert("/posts/:page?", 2).unwrap();
+    router.insert("/static/*path?", 3).unwrap();
+    router.insert("/v1/projects/:id::batchGet", 4).unwrap();
+
+    let json = serde_json::to_string(&router).unwrap();
+    assert_eq!(
+        json,
+        r#"[["/",0],["/users/:id",1],["/posts/:page?",2],["/static/*path?",3],["/v1/projects/:id::batchGet",4]]"#
+    );
+
+    let router: Router<u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(router.iter().count(), 5);
+    assert_eq!(router.at("/users/1").unwrap().params.get("id"), Some("1"));
+    assert_eq!(*router.at("/posts").unwrap().value, 2);
+    assert_eq!(*router.at("/static/").unwrap().value, 3);
+    assert_eq!(*router.at("/v1/projects/x:batchGet").unwrap().value, 4);
+    router.check_priorities().unwrap();
+}
+
+#[test]
+fn conflict() {
+    let err = serde_json::from_str::<Router<u32>>(r#"[["/users/:id", 1], ["/users/:user_id", 2]]"#)
+        .err()
+        .unwrap();
+
+    assert!(err.to_string().starts_with(
+        "insertion of '/users/:user_id' failed due to conflict with previously registered \
+         route '/users/:id'"
+    ));
+}
+
+#[test]
+fn constraints() {
+    let json = r#"[["/users/:id<num>", 1]]"#;
+
+    let err = serde_json::from_str::<Router<u32>>(json).err().unwrap();
+    assert!(err
+        .to_string()
+        .starts_with("no constraint named 'num' is registered"));
+
+    let mut router = Router::new();
+    router.constraint("num", |id| id.parse::<u64>().is_ok());
+    router.insert("/", 0).unwrap();
+    (&mut router)
+        .deserialize(&mut serde_json::Deserializer::from_str(json))
+        .unwrap();
+
+    assert_eq!(*router.at("/users/1").unwrap().value, 1);
+    assert!(router.at("/users/x").is_err());
+    assert_eq!(*router.at("/").unwrap().value, 0);
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "tower")]
+
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+
+use http::{Request, Response, StatusCode};
+use matchit::{MatchError, OwnedParams, Router, RouterService};
+use tower::util::BoxCloneService;
+use tower::{service_fn, Service, ServiceExt};
+
+type Handler = BoxCloneService<Request<()>, Response<String>, Infallible>;
+
+fn handler(name: &'static str) -> Handler {
+    BoxCloneService::new(service_fn(move |req: Request<()>| async move {
+        let params = req.extensions().get::<OwnedParams>().unwrap();
+        let params = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>();
+
+        Ok(Response::new(format!("{} {}", name, params.join(","))))
+    }))
+}
+
+async fn call(service: &RouterService<Handler>, path: &str) -> Response<String> {
+    let req = Request::get(path).body(()).unwrap();
+    service.clone().oneshot(req).await.unwrap()
+}
+
+#[tokio::test]
+async fn dispatch() {
+    let mut router = Router::new();
+    router.insert("/", handler("index")).unwrap();
+    router.insert("/users/:id", handler("user")).unwrap();
+    router.insert("/:org/:repo/*path", handler("file")).unwrap();
+    let service = RouterService::new(router);
+
+    assert_eq!(call(&service, "/").await.body(), "index ");
+    assert_eq!(call(&service, "/users/1").await.body(), "user id=1");
+    assert_eq!(
+        call(&service, "/rust-lang/rust/src/lib.rs?x=1")
+            .await
+            .body(),
+        "file org=rust-lang,repo=rust,path=src/lib.rs"
+    );
+
+    let response = call(&service, "/users").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.body(), "");
+}
+
+#[tokio::test]
+async fn fallback() {
+    let mut router = Router::new();
+    router.insert("/home", handler("home")).unwrap();
+
+    let fallback = BoxCloneService::new(service_fn(|req: Request<()>| async move {
+        let err = req.extensions().get::<MatchError>().unwrap();
+        Ok(Response::new(err.to_string()))
+    }));
+    let service = RouterService::new(router).fallback(fallback);
+
+    assert_eq!(call(&service, "/home").await.body(), "home ");
+    assert_eq!(
+        *call(&service, "/home/").await.body(),
+        MatchError::ExtraTrailingSlash.to_string()
+    );
+    assert_eq!(
+        *call(&service, "/about").await.body(),
+        MatchError::NotFound.to_string()
+    );
+}
+
+// a service that is only ready after being polled a few times
+#[derive(Clone)]
+struct Slow {
+    polls: usize,
+}
+
+impl Service<Request<()>> for Slow {
+    type Response = Response<String>;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Response<String>, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        if self.polls < 3 {
+            self.polls += 1;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Request<()>) -> Self::Future {
+        assert_eq!(self.polls, 3, "called before the service was ready");
+        std::future::ready(Ok(Response::new(String::from("ready"))))
+    }
+}
+
+#[tokio::test]
+async fn poll_ready() {
+    let mut router = Router::new();
+    router.insert("/", Slow { polls: 0 }).unwrap();
+    let mut service = RouterService::new(router);
+
+    // the router is always ready, the matched service is readied for each request
+    for _ in 0..2 {
+        let service = service.ready().await.unwrap();
+        let response = service.call(Request::new(())).await.unwrap();
+        assert_eq!(response.body(), "ready");
+    }
+}
@@ -1,4 +1,32 @@
-use matchit::{InsertError, MatchError, Router};
+use matchit::ConflictKind::{self, CatchAll, Constraint, Duplicate, ParamName};
+use matchit::{
+    DecodeError, FrozenError, FrozenRouter, HostRouter, InsertError, MatchError, MethodError,
+    MethodRouter, ParamError, Router, UrlError,
+};
+
+fn conflict(route: &str, with: &str, segment: &str, kind: ConflictKind) -> InsertError {
+    InsertError::Conflict {
+        route: route.into(),
+        with: with.into(),
+        segment: segment.into(),
+        kind,
+    }
+}
+
+// Checks that a frozen copy of the router, loaded back from its bytes, matches the path the same way.
+fn assert_frozen_matches(router: &Router<String>, path: &str) {
+    let frozen = router.freeze().unwrap();
+    let frozen = FrozenRouter::from_bytes(frozen.as_bytes()).unwrap();
+
+    let expected = router
+        .at(path)
+        .map(|m| (m.route(), m.params.iter().collect::<Vec<_>>()));
+    let got = frozen
+        .at(path)
+        .map(|m| (m.route(), m.params.iter().collect::<Vec<_>>()));
+
+    assert_eq!(got, expected, "frozen router mismatch for '{}'", path);
+}
 
 #[test]
 fn issue_31() {
@@ -363,6 +391,64 @@ match_tests! {
         ],
         "/yyy/y"  :: "/yyy/*x" => { "x" => "y" },
         "/yyy/"   :: "/yyy*x"  => { "x" => "/"},
+    },
+    suffixes {
+        routes = [
+            "/files/:name.:ext",
+            "/files/:name",
+            "/files/:name.json",
+            "/files/:name/raw",
+            "/:from-:to",
+            "/v:major.:minor/docs",
+            "/archives/:name.tar.gz",
+            "/archives/:name.*rest",
+            "/users/:id-:slug/",
+            "/dl/:name.zip",
+        ],
+        "/files/foo.txt"             :: "/files/:name.:ext"      => { "name" => "foo", "ext" => "txt" },
+        "/files/foo.tar.gz"          :: "/files/:name.:ext"      => { "name" => "foo", "ext" => "tar.gz" },
+        "/files/foo.json"            :: "/files/:name.json"      => { "name" => "foo" },
+        "/files/foo.json.json"       :: "/files/:name.:ext"      => { "name" => "foo", "ext" => "json.json" },
+        "/files/foo"                 :: "/files/:name"           => { "name" => "foo" },
+        "/files/foo/raw"             :: "/files/:name/raw"       => { "name" => "foo" },
+        "/files/foo.txt/raw"         :: "/files/:name/raw"       => { "name" => "foo.txt" },
+        "/files/.txt"                :: "/files/:name"           => { "name" => ".txt" },
+        "/files/foo."                :: "/files/:name"           => { "name" => "foo." },
+        "/berlin-paris"              :: "/:from-:to"             => { "from" => "berlin", "to" => "paris" },
+        "/a-b-c"                     :: "/:from-:to"             => { "from" => "a", "to" => "b-c" },
+        "/berlin"                    :: ""                       => None,
+        "/v1.2/docs"                 :: "/v:major.:minor/docs"   => { "major" => "1", "minor" => "2" },
+        "/v1.2.3/docs"               :: "/v:major.:minor/docs"   => { "major" => "1", "minor" => "2.3" },
+        "/v1/docs"                   :: ""                       => None,
+        "/archives/x.tar.gz"         :: "/archives/:name.tar.gz" => { "name" => "x" },
+        "/archives/x.tar.gz.tar.gz"  :: "/archives/:name.*rest"  => { "name" => "x", "rest" => "tar.gz.tar.gz" },
+        "/dl/a.zip"                  :: "/dl/:name.zip"          => { "name" => "a" },
+        "/dl/a.zip.zip"              :: "/dl/:name.zip"          => { "name" => "a.zip" },
+        "/dl/a.b.zip"                :: "/dl/:name.zip"          => { "name" => "a.b" },
+        "/dl/a.zip.gz"               :: ""                       => None,
+        "/archives/x.tar.bz2"        :: "/archives/:name.*rest"  => { "name" => "x", "rest" => "tar.bz2" },
+        "/archives/x.y/z"            :: "/archives/:name.*rest"  => { "name" => "x", "rest" => "y/z" },
+        "/users/1-ferris/"           :: "/users/:id-:slug/"      => { "id" => "1", "slug" => "ferris" },
+        "/users/1-ferris"            :: ""                       => None,
+    },
+    escaped {
+        routes = [
+            "/v1/projects/:id::batchGet",
+            "/v1/projects/:id",
+            "/files/**.tar",
+            "/files/:name",
+            "/static::/*path",
+            "/ratio/:w:::h",
+        ],
+        "/v1/projects/abc:batchGet"  :: "/v1/projects/:id::batchGet" => { "id" => "abc" },
+        "/v1/projects/abc"           :: "/v1/projects/:id"           => { "id" => "abc" },
+        "/v1/projects/abc:batch"     :: "/v1/projects/:id"           => { "id" => "abc:batch" },
+        "/files/*.tar"               :: "/files/**.tar"              => {},
+        "/files/x.tar"               :: "/files/:name"               => { "name" => "x.tar" },
+        "/static:/css/main.css"      :: "/static::/*path"            => { "path" => "css/main.css" },
+        "/static/css"                :: ""                           => None,
+        "/ratio/16:9"                :: "/ratio/:w:::h"              => { "w" => "16", "h" => "9" },
+        "/ratio/16"                  :: ""                           => None,
     }
 }
 
@@ -387,18 +473,634 @@ fn issue_12() {
     assert_eq!(matched.params.get("id"), Some("978"));
 }
 
+#[test]
+fn iter_routes() {
+    let routes = [
+        "/",
+        "/cmd/:tool/",
+        "/cmd/:tool2/:sub",
+        "/cmd/whoami",
+        "/src/*filepath",
+        "/search/:query",
+        "/search/actix-web",
+        "/user_:name",
+        "/user_:name/about",
+        "/files/:dir/*filepath",
+        "/x/:foo/bar",
+        "/x/:bar/baz",
+        "/:foo/:baz/bax",
+        "/:fod/:baz/:bax/foo",
+        "/:fod/baz/bax/foo",
+    ];
+
+    let mut router = Router::new();
+    for route in routes {
+        router.insert(route, route.to_owned()).unwrap();
+    }
+
+    let mut expected = routes.to_vec();
+    expected.sort_unstable();
+
+    let mut found = router.iter().collect::<Vec<_>>();
+    found.sort_unstable();
+    assert!(found.iter().all(|(route, value)| route == *value));
+    assert_eq!(
+        found.iter().map(|(route, _)| route).collect::<Vec<_>>(),
+        expected
+    );
+
+    for (route, value) in &mut router {
+        value.push_str(&route);
+    }
+
+    let mut found = router.into_iter().collect::<Vec<_>>();
+    found.sort_unstable();
+    assert!(found.iter().all(|(route, value)| *value == route.repeat(2)));
+    assert_eq!(
+        found.iter().map(|(route, _)| route).collect::<Vec<_>>(),
+        expected
+    );
+
+    assert_eq!(Router::<()>::new().iter().count(), 0);
+}
+
+#[test]
+fn nest() {
+    let mut users = Router::new();
+    users.insert("/", "users").unwrap();
+    users.insert("/:id", "user").unwrap();
+    users.insert("/:id/posts/*rest", "posts").unwrap();
+
+    let mut router = Router::new();
+    router.insert("/", "root").unwrap();
+    router.insert("/users/me", "me").unwrap();
+    router.nest("/users", users).unwrap();
+
+    assert_eq!(router.at("/").map(|m| *m.value), Ok("root"));
+    assert_eq!(router.at("/users/").map(|m| *m.value), Ok("users"));
+    assert_eq!(router.at("/users/me").map(|m| *m.value), Ok("me"));
+    assert_eq!(router.at("/users/1").map(|m| *m.value), Ok("user"));
+
+    let matched = router.at("/users/1/posts/x/y").unwrap();
+    assert_eq!(*matched.value, "posts");
+    assert_eq!(matched.params.get("id"), Some("1"));
+    assert_eq!(matched.params.get("rest"), Some("x/y"));
+    assert_eq!(matched.route(), "/users/:id/posts/*rest");
+
+    router.check_priorities().unwrap();
+}
+
+#[test]
+fn nest_trailing_slash() {
+    let mut users = Router::new();
+    users.insert("/", "users").unwrap();
+    users.insert_named("user", "/:id", "user").unwrap();
+
+    let mut router = Router::new();
+    router.nest("/api/", users).unwrap();
+
+    assert_eq!(router.at("/api/").map(|m| *m.value), Ok("users"));
+    assert_eq!(router.at("/api/1").map(|m| *m.value), Ok("user"));
+    assert!(router.at("/api//").is_err());
+    assert_eq!(router.url_for("user", [("id", "1")]).unwrap(), "/api/1");
+
+    router.check_priorities().unwrap();
+}
+
+#[test]
+fn nest_conflict() {
+    let mut router = Router::new();
+    router.insert("/api/users/:id", "user").unwrap();
+    router.insert("/api/static/*path", "static").unwrap();
+
+    let mut api = Router::new();
+    api.insert("/health", "health").unwrap();
+    api.insert("/users/:user_id", "conflict").unwrap();
+    api.insert("/static/:file", "conflict").unwrap();
+
+    let before = router.clone();
+    let err = router.nest("/api", api).unwrap_err();
+    assert!(matches!(err, InsertError::Conflict { .. }), "{:?}", err);
+
+    assert!(router.at("/api/health").is_err());
+    assert_eq!(router.at("/api/users/1").map(|m| *m.value), Ok("user"));
+    assert_eq!(router.iter().count(), before.iter().count());
+    router.check_priorities().unwrap();
+}
+
+#[test]
+fn conflict_message() {
+    let mut router = Router::new();
+    router.insert("/users/:id/posts", "posts").unwrap();
+
+    let err = router.insert("/users/:user_id/posts", "user").unwrap_err();
+    assert_eq!(
+        err,
+        conflict(
+            "/users/:user_id/posts",
+            "/users/:id/posts",
+            ":user_id",
+            ParamName
+        )
+    );
+    assert_eq!(
+        err.to_string(),
+        "insertion of '/users/:user_id/posts' failed due to conflict with previously registered \
+         route '/users/:id/posts': the parameter in ':user_id' is named differently"
+    );
+}
+
+#[test]
+fn conflict_with() {
+    let mut router = Router::new();
+    router.insert("/x/:id/long/path/one", 1).unwrap();
+    router.insert("/x/:id/long/path/two", 2).unwrap();
+    router.insert("/x/:id/b", 3).unwrap();
+
+    // the shortest route through the colliding parameter, not the one with the highest priority
+    assert_eq!(
+        router.insert("/x/*rest", 4),
+        Err(conflict("/x/*rest", "/x/:id/b", "*rest", CatchAll))
+    );
+
+    // a catch-all and a static segment don't conflict
+    router.insert("/static/*path", 9).unwrap();
+    router.insert("/static/readme", 10).unwrap();
+    assert_eq!(router.at("/static/readme").map(|m| *m.value), Ok(10));
+    assert_eq!(router.at("/static/other").map(|m| *m.value), Ok(9));
+
+    // routes are reported as they were inserted
+    router.insert("/files/**.tar", 5).unwrap();
+    router.insert("/files/:name/:page?", 6).unwrap();
+
+    assert_eq!(
+        router.insert("/files/**.tar", 7),
+        Err(conflict(
+            "/files/**.tar",
+            "/files/**.tar",
+            "**.tar",
+            Duplicate
+        ))
+    );
+    assert_eq!(
+        router.insert("/files/:file", 8),
+        Err(conflict(
+            "/files/:file",
+            "/files/:name/:page?",
+            ":file",
+            ParamName
+        ))
+    );
+}
+
+#[test]
+fn shared_param_positions() {
+    let mut router = Router::new();
+    router.insert("/users/:id", "user").unwrap();
+    router.insert("/users/:user_id/posts", "posts").unwrap();
+    router.insert("/users/:uid/posts/:id", "post").unwrap();
+    router.insert("/archive/:year?", "archive").unwrap();
+    router.insert("/archive/:y/:m", "month").unwrap();
+
+    let matched = router.at("/users/1").unwrap();
+    assert_eq!(matched.route(), "/users/:id");
+    assert!(matched.params.iter().eq([("id", "1")]));
+
+    let matched = router.at("/users/1/posts").unwrap();
+    assert_eq!(matched.route(), "/users/:user_id/posts");
+    assert!(matched.params.iter().eq([("user_id", "1")]));
+
+    let matched = router.at("/users/1/posts/2").unwrap();
+    assert_eq!(matched.route(), "/users/:uid/posts/:id");
+    assert!(matched.params.iter().eq([("uid", "1"), ("id", "2")]));
+
+    let matched = router.at("/archive/2022/12").unwrap();
+    assert!(matched.params.iter().eq([("y", "2022"), ("m", "12")]));
+    assert_eq!(
+        router.at("/archive/2022").unwrap().params.get("year"),
+        Some("2022")
+    );
+
+    // the names of a route are only used to find that route
+    assert_eq!(router.remove("/users/:user_id"), None);
+    assert_eq!(router.remove("/users/:id"), Some("user"));
+    let matched = router.at("/users/1/posts").unwrap();
+    assert!(matched.params.iter().eq([("user_id", "1")]));
+    router.check_priorities().unwrap();
+}
+
+#[test]
+fn many_params() {
+    let route = |n: usize| (0..n).map(|i| format!("/:p{}", i)).collect::<String>();
+    let path = |n: usize| (0..n).map(|i| format!("/{}", i)).collect::<String>();
+
+    let mut router = Router::new();
+    router.insert(route(100), "params").unwrap();
+    router
+        .insert(format!("{}/:p.json", route(100)), "json")
+        .unwrap();
+    router
+        .insert(format!("{}/files/*rest", route(99)), "rest")
+        .unwrap();
+    router
+        .insert(format!("{}/:x/:y", route(40)), "names")
+        .unwrap();
+
+    router.check_priorities().unwrap();
+
+    let full = path(100);
+    let matched = router.at(&full).unwrap();
+    assert_eq!(*matched.value, "params");
+    assert_eq!(matched.params.len(), 100);
+    for (i, (key, value)) in matched.params.iter().enumerate() {
+        assert_eq!(key, format!("p{}", i));
+        assert_eq!(value, i.to_string());
+    }
+
+    let json = format!("{}/x.json", path(100));
+    let matched = router.at(&json).unwrap();
+    assert_eq!(*matched.value, "json");
+    assert_eq!(matched.params.get("p"), Some("x"));
+
+    let rest = format!("{}/files/a/b", path(99));
+    let matched = router.at(&rest).unwrap();
+    assert_eq!(*matched.value, "rest");
+    assert_eq!(matched.params.get("rest"), Some("a/b"));
+
+    let names = path(42);
+    let matched = router.at(&names).unwrap();
+    assert_eq!(*matched.value, "names");
+    assert_eq!(matched.params.get("y"), Some("41"));
+
+    assert_eq!(
+        router.insert(format!("{}/:q.json", route(100)), "x"),
+        Err(conflict(
+            &format!("{}/:q.json", route(100)),
+            &format!("{}/:p.json", route(100)),
+            ":q.json",
+            ParamName
+        ))
+    );
+    assert_eq!(router.remove(route(100)), Some("params"));
+    assert!(router.at(&full).is_err());
+}
+
+#[test]
+fn merge() {
+    let mut router = Router::new();
+    router.insert("/", "root").unwrap();
+    router.insert("/users/:id", "user").unwrap();
+    router.insert("/static/*path", "static").unwrap();
+
+    let mut other = Router::new();
+    other.insert("/", "conflict").unwrap();
+    other.insert("/users/:user_id/posts", "posts").unwrap();
+    other.insert("/users/:user", "conflict").unwrap();
+    other.insert("/static/:file", "conflict").unwrap();
+    other.insert("/about", "about").unwrap();
+
+    let mut errors = router.merge(other).unwrap_err().into_errors();
+    errors.sort();
+
+    assert_eq!(
+        errors,
+        [
+            ("/".to_owned(), conflict("/", "/", "", Duplicate)),
+            (
+                "/static/:file".to_owned(),
+                conflict("/static/:file", "/static/*path", ":file", CatchAll)
+            ),
+            (
+                "/users/:user".to_owned(),
+                conflict("/users/:user", "/users/:id", ":user", ParamName)
+            ),
+        ]
+    );
+
+    assert_eq!(router.at("/").map(|m| *m.value), Ok("root"));
+    assert_eq!(router.at("/about").map(|m| *m.value), Ok("about"));
+    assert_eq!(router.at("/users/1").map(|m| *m.value), Ok("user"));
+    assert_eq!(router.at("/users/1/posts").map(|m| *m.value), Ok("posts"));
+    assert_eq!(router.at("/static/x").map(|m| *m.value), Ok("static"));
+    assert_eq!(router.iter().count(), 5);
+    router.check_priorities().unwrap();
+
+    let mut other = Router::new();
+    other.insert("/contact", "contact").unwrap();
+    assert_eq!(router.merge(other), Ok(()));
+    assert_eq!(router.at("/contact").map(|m| *m.value), Ok("contact"));
+}
+
+#[test]
+fn url_for() {
+    let mut router = Router::new();
+    router.insert_named("home", "/", "home").unwrap();
+    router.insert_named("user", "/users/:id", "user").unwrap();
+    router
+        .insert_named("post", "/users/:id/posts/:post_id/", "post")
+        .unwrap();
+    router
+        .insert_named("file", "/files/:dir/*path", "file")
+        .unwrap();
+    router
+        .insert_named("mixed", "/user_:name.json", "mixed")
+        .unwrap();
+    router
+        .insert_named("escaped", "/v1/projects/:id::batchGet", "escaped")
+        .unwrap();
+
+    let url = |name: &str, params: &[(&str, &str)]| router.url_for(name, params.iter().copied());
+
+    assert_eq!(url("home", &[]), Ok("/".to_owned()));
+    assert_eq!(url("user", &[("id", "1")]), Ok("/users/1".to_owned()));
+    assert_eq!(
+        url("post", &[("post_id", "2"), ("id", "1")]),
+        Ok("/users/1/posts/2/".to_owned())
+    );
+    assert_eq!(
+        url("file", &[("dir", "src"), ("path", "tree/mod.rs")]),
+        Ok("/files/src/tree/mod.rs".to_owned())
+    );
+    assert_eq!(
+        url("mixed", &[("name", "ferris")]),
+        Ok("/user_ferris.json".to_owned())
+    );
+    assert_eq!(
+        url("escaped", &[("id", "abc")]),
+        Ok("/v1/projects/abc:batchGet".to_owned())
+    );
+
+    assert_eq!(url("users", &[]), Err(UrlError::UnknownRoute));
+    assert_eq!(
+        url("user", &[]),
+        Err(UrlError::MissingParam { name: "id".into() })
+    );
+    assert_eq!(
+        url("user", &[("id", "1"), ("id", "2")]),
+        Err(UrlError::ExtraParam { name: "id".into() })
+    );
+    assert_eq!(
+        url("home", &[("id", "1")]),
+        Err(UrlError::ExtraParam { name: "id".into() })
+    );
+    assert_eq!(
+        url("user", &[("id", "1/2")]),
+        Err(UrlError::InvalidParam { name: "id".into() })
+    );
+    assert_eq!(
+        url("file", &[("dir", "src"), ("path", "")]),
+        Err(UrlError::InvalidParam {
+            name: "path".into()
+        })
+    );
+
+    // every generated url matches the route it was built from
+    for (path, name) in [
+        ("/users/1", "user"),
+        ("/users/1/posts/2/", "post"),
+        ("/files/src/tree/mod.rs", "file"),
+        ("/user_ferris.json", "mixed"),
+        ("/v1/projects/abc:batchGet", "escaped"),
+    ] {
+        let matched = router.at(path).unwrap();
+        assert_eq!(router.url_for(name, matched.params.iter()).unwrap(), path);
+    }
+}
+
+#[test]
+fn named_routes() {
+    let mut router = Router::new();
+    router.insert_named("user", "/users/:id", "user").unwrap();
+    assert_eq!(
+        router.insert_named("user", "/user/:id", "user"),
+        Err(InsertError::DuplicateName {
+            name: "user".into()
+        })
+    );
+    assert!(router.at("/user/1").is_err());
+
+    // removing a route removes its name
+    assert_eq!(router.remove("/users/:id"), Some("user"));
+    assert_eq!(
+        router.url_for("user", [("id", "1")]),
+        Err(UrlError::UnknownRoute)
+    );
+    router.insert_named("user", "/user/:id", "user").unwrap();
+
+    // names are kept when nesting routers
+    let mut api = Router::new();
+    api.insert_named("post", "/posts/:id", "post").unwrap();
+    router.nest("/api", api).unwrap();
+    assert_eq!(
+        router.url_for("post", [("id", "1")]),
+        Ok("/api/posts/1".to_owned())
+    );
+
+    let mut api = Router::new();
+    api.insert_named("user", "/users/:id", "user").unwrap();
+    assert_eq!(
+        router.nest("/api", api),
+        Err(InsertError::DuplicateName {
+            name: "user".into()
+        })
+    );
+
+    // and when merging
+    let mut other = Router::new();
+    other.insert_named("about", "/about", "about").unwrap();
+    other.insert_named("user", "/users/:id", "user").unwrap();
+    assert_eq!(
+        router.merge(other).unwrap_err().into_errors(),
+        [(
+            "/users/:id".to_owned(),
+            InsertError::DuplicateName {
+                name: "user".into()
+            }
+        )]
+    );
+    assert_eq!(
+        router.url_for("about", [("", ""); 0]),
+        Ok("/about".to_owned())
+    );
+    assert!(router.at("/users/1").is_err());
+}
+
+#[test]
+fn path_ignore_case() {
+    let routes = [
+        "/hi",
+        "/b/",
+        "/ABC/",
+        "/search/:query",
+        "/cmd/:tool/",
+        "/src/*filepath",
+        "/x",
+        "/x/y",
+        "/y/",
+        "/y/z",
+        "/0/:id",
+        "/0/:id/1",
+        "/1/:id/",
+        "/1/:id/2",
+        "/aa",
+        "/a/",
+        "/doc",
+        "/doc/rust_faq.html",
+        "/doc/rust1.26.html",
+        "/doc/rust-is-ÅWESOME.html",
+        "/Straße",
+        "/ß",
+        "/é/è",
+        "/ée",
+    ];
+
+    let mut router = Router::new();
+    for route in routes {
+        router.insert(route, ()).unwrap();
+    }
+
+    // routes match themselves, with or without a fixed trailing slash
+    for route in routes {
+        for fix_trailing_slash in [true, false] {
+            let found = router.path_ignore_case(route, fix_trailing_slash);
+            assert_eq!(
+                found.as_deref(),
+                Some(route),
+                "wrong result for '{}'",
+                route
+            );
+        }
+    }
+
+    for (path, fix_trailing_slash, expected) in [
+        ("/HI", false, Some("/hi")),
+        ("/HI/", false, None),
+        ("/HI/", true, Some("/hi")),
+        ("/B", true, Some("/b/")),
+        ("/b", false, None),
+        ("/abc/", false, Some("/ABC/")),
+        ("/aBc", true, Some("/ABC/")),
+        ("/SEARCH/QUERY", false, Some("/search/QUERY")),
+        ("/SEARCH/QUERY/", true, Some("/search/QUERY")),
+        ("/CMD/TOOL/", false, Some("/cmd/TOOL/")),
+        ("/CMD/TOOL", true, Some("/cmd/TOOL/")),
+        ("/SRC/FILE/PATH", false, Some("/src/FILE/PATH")),
+        ("/x/Y", false, Some("/x/y")),
+        ("/X/y/", true, Some("/x/y")),
+        ("/Y/", false, Some("/y/")),
+        ("/Y/Z/", true, Some("/y/z")),
+        ("/0/1/1", false, Some("/0/1/1")),
+        ("/1/ID/2/", true, Some("/1/ID/2")),
+        ("/AA", false, Some("/aa")),
+        ("/A", true, Some("/a/")),
+        ("/DOC/RUST_FAQ.HTML", false, Some("/doc/rust_faq.html")),
+        (
+            "/DOC/RUST-IS-åwesome.HTML",
+            false,
+            Some("/doc/rust-is-ÅWESOME.html"),
+        ),
+        ("/STRASSE", false, None),
+        ("/straße", false, Some("/Straße")),
+        ("/ẞ", false, Some("/ß")),
+        ("/É/È", false, Some("/é/è")),
+        ("/ÉE", false, Some("/ée")),
+        ("/nope", true, None),
+        ("/0", true, None),
+        ("/", true, None),
+        ("", true, None),
+    ] {
+        let found = router.path_ignore_case(path, fix_trailing_slash);
+        assert_eq!(found.as_deref(), expected, "wrong result for '{}'", path);
+    }
+}
+
+#[test]
+fn fix_path() {
+    let mut router = Router::new();
+    for route in [
+        "/",
+        "/hi",
+        "/b/",
+        "/search/:query",
+        "/cmd/:tool/",
+        "/src/*filepath",
+    ] {
+        router.insert(route, ()).unwrap();
+    }
+
+    for (path, expected) in [
+        ("/", None),
+        ("/hi", None),
+        ("/hi/", Some("/hi")),
+        ("/b", Some("/b/")),
+        ("//hi", Some("/hi")),
+        ("/b/./", Some("/b/")),
+        ("/b/.", Some("/b/")),
+        ("/x/../hi", Some("/hi")),
+        ("/hi/x/..", Some("/hi")),
+        ("/../b", Some("/b/")),
+        ("/search//rust/", Some("/search/rust")),
+        ("/cmd/vet", Some("/cmd/vet/")),
+        ("/src/a//b", Some("/src/a/b")),
+        ("/src/a/b", None),
+        ("", Some("/")),
+        ("/nope", None),
+        ("/nope/", None),
+    ] {
+        assert_eq!(
+            router.fix_path(path).as_deref(),
+            expected,
+            "wrong result for '{}'",
+            path
+        );
+    }
+}
+
+#[test]
+fn decoded_params() {
+    let mut router = Router::new();
+    router.insert("/users/:name", "user").unwrap();
+    router.insert("/users/:name/posts", "posts").unwrap();
+    router.insert("/files/*path", "files").unwrap();
+
+    // encoded slashes never separate segments
+    let matched = router.at("/users/a%2Fposts").unwrap();
+    assert_eq!(*matched.value, "user");
+    assert_eq!(
+        matched.params.get_decoded("name"),
+        Some(Ok("a/posts".into()))
+    );
+
+    let matched = router.at("/users/a%20b/posts").unwrap();
+    assert_eq!(*matched.value, "posts");
+    assert_eq!(matched.params.get_decoded("name"), Some(Ok("a b".into())));
+    assert_eq!(matched.params.get_decoded("id"), None);
+
+    let matched = router.at("/files/a%2Fb/c%25").unwrap();
+    assert_eq!(
+        matched.params.get_decoded("path"),
+        Some(Ok("a/b/c%".into()))
+    );
+
+    let matched = router.at("/users/%E2%28").unwrap();
+    assert_eq!(
+        matched.params.get_decoded("name"),
+        Some(Err(DecodeError::InvalidUtf8))
+    );
+}
+
 insert_tests! {
     wildcard_conflict {
         "/cmd/:tool/:sub"     => Ok(()),
         "/cmd/vet"            => Ok(()),
         "/foo/bar"            => Ok(()),
         "/foo/:name"          => Ok(()),
-        "/foo/:names"         => Err(InsertError::Conflict { with: "/foo/:name".into() }),
-        "/cmd/*path"          => Err(InsertError::Conflict { with: "/cmd/:tool/:sub".into() }),
+        "/foo/:names"         => Err(conflict("/foo/:names", "/foo/:name", ":names", ParamName)),
+        "/cmd/*path"          => Err(conflict("/cmd/*path", "/cmd/:tool/:sub", "*path", CatchAll)),
         "/cmd/:xxx/names"     => Ok(()),
         "/cmd/:tool/:xxx/foo" => Ok(()),
         "/src/*filepath"      => Ok(()),
-        "/src/:file"          => Err(InsertError::Conflict { with: "/src/*filepath".into() }),
+        "/src/:file"          => Err(conflict("/src/:file", "/src/*filepath", ":file", CatchAll)),
         "/src/static.json"    => Ok(()),
         "/src/$filepathx"     => Ok(()),
         "/src/"               => Ok(()),
@@ -415,7 +1117,7 @@ insert_tests! {
         "/search/valid"       => Ok(()),
         "/user_:name"         => Ok(()),
         "/user_x"             => Ok(()),
-        "/user_:bar"          => Err(InsertError::Conflict { with: "/user_:name".into() }),
+        "/user_:bar"          => Err(conflict("/user_:bar", "/user_:name", "user_:bar", ParamName)),
         "/id:id"              => Ok(()),
         "/id/:id"             => Ok(()),
     },
@@ -438,7 +1140,7 @@ insert_tests! {
         "/cmd/:tool"      => Ok(()),
         "/cmd/:tool/:sub" => Ok(()),
         "/cmd/:tool/misc" => Ok(()),
-        "/cmd/:tool/:bad" => Err(InsertError::Conflict { with: "/cmd/:tool/:sub".into() }),
+        "/cmd/:tool/:bad" => Err(conflict("/cmd/:tool/:bad", "/cmd/:tool/:sub", ":bad", ParamName)),
         "/src/AUTHORS"    => Ok(()),
         "/src/*filepath"  => Ok(()),
         "/user_x"         => Ok(()),
@@ -446,19 +1148,19 @@ insert_tests! {
         "/id/:id"         => Ok(()),
         "/id:id"          => Ok(()),
         "/:id"            => Ok(()),
-        "/*filepath"      => Err(InsertError::Conflict { with: "/:id".into() }),
+        "/*filepath"      => Err(conflict("/*filepath", "/:id", "*filepath", CatchAll)),
     },
     duplicates {
         "/"              => Ok(()),
-        "/"              => Err(InsertError::Conflict { with: "/".into() }),
+        "/"              => Err(conflict("/", "/", "", Duplicate)),
         "/doc/"          => Ok(()),
-        "/doc/"          => Err(InsertError::Conflict { with: "/doc/".into() }),
+        "/doc/"          => Err(conflict("/doc/", "/doc/", "doc", Duplicate)),
         "/src/*filepath" => Ok(()),
-        "/src/*filepath" => Err(InsertError::Conflict { with: "/src/*filepath".into() }),
+        "/src/*filepath" => Err(conflict("/src/*filepath", "/src/*filepath", "*filepath", Duplicate)),
         "/search/:query" => Ok(()),
-        "/search/:query" => Err(InsertError::Conflict { with: "/search/:query".into() }),
+        "/search/:query" => Err(conflict("/search/:query", "/search/:query", ":query", Duplicate)),
         "/user_:name"    => Ok(()),
-        "/user_:name"    => Err(InsertError::Conflict { with: "/user_:name".into() }),
+        "/user_:name"    => Err(conflict("/user_:name", "/user_:name", "user_:name", Duplicate)),
     },
     unnamed_param {
         "/user:"  => Err(InsertError::UnnamedParam),
@@ -473,11 +1175,11 @@ insert_tests! {
     },
     normalized_conflict {
         "/x/:foo/bar"  => Ok(()),
-        "/x/:bar/bar"  => Err(InsertError::Conflict { with: "/x/:foo/bar".into() }),
+        "/x/:bar/bar"  => Err(conflict("/x/:bar/bar", "/x/:foo/bar", ":bar", ParamName)),
         "/:y/bar/baz"  => Ok(()),
         "/:y/baz/baz"  => Ok(()),
         "/:z/bar/bat"  => Ok(()),
-        "/:z/bar/baz"  => Err(InsertError::Conflict { with: "/:y/bar/baz".into() }),
+        "/:z/bar/baz"  => Err(conflict("/:z/bar/baz", "/:y/bar/baz", ":z", ParamName)),
     },
     more_conflicts {
         "/con:tact"           => Ok(()),
@@ -486,8 +1188,8 @@ insert_tests! {
         "/whose/:users/:name" => Ok(()),
         "/who/are/foo"        => Ok(()),
         "/who/are/foo/bar"    => Ok(()),
-        "/con:nection"        => Err(InsertError::Conflict { with: "/con:tact".into() }),
-        "/whose/:users/:user" => Err(InsertError::Conflict { with: "/whose/:users/:name".into() }),
+        "/con:nection"        => Err(conflict("/con:nection", "/con:tact", "con:nection", ParamName)),
+        "/whose/:users/:user" => Err(conflict("/whose/:users/:user", "/whose/:users/:name", ":user", ParamName)),
     },
     catchall_static_overlap1 {
         "/bar"      => Ok(()),
@@ -501,16 +1203,16 @@ insert_tests! {
         "/baz"            => Ok(()),
         "/baz/:split"     => Ok(()),
         "/"               => Ok(()),
-        "/*bar"           => Err(InsertError::Conflict { with: "/*bar".into() }),
-        "/*zzz"           => Err(InsertError::Conflict { with: "/*bar".into() }),
-        "/:xxx"           => Err(InsertError::Conflict { with: "/*bar".into() }),
+        "/*bar"           => Err(conflict("/*bar", "/*bar", "*bar", Duplicate)),
+        "/*zzz"           => Err(conflict("/*zzz", "/*bar", "*zzz", ParamName)),
+        "/:xxx"           => Err(conflict("/:xxx", "/*bar", ":xxx", CatchAll)),
     },
     catchall_static_overlap3 {
         "/*bar"           => Ok(()),
         "/bar"            => Ok(()),
         "/bar/x"          => Ok(()),
         "/bar_:x"         => Ok(()),
-        "/bar_:x"         => Err(InsertError::Conflict { with: "/bar_:x".into() }),
+        "/bar_:x"         => Err(conflict("/bar_:x", "/bar_:x", "bar_:x", Duplicate)),
         "/bar_:x/y"       => Ok(()),
         "/bar/:x"         => Ok(()),
     },
@@ -518,10 +1220,184 @@ insert_tests! {
         "/hey" => Ok(()),
         "/hey/users" => Ok(()),
         "/hey/user" => Ok(()),
-        "/hey/user" => Err(InsertError::Conflict { with: "/hey/user".into() }),
+        "/hey/user" => Err(conflict("/hey/user", "/hey/user", "user", Duplicate)),
+    },
+    suffix_params {
+        "/:name.:ext"      => Ok(()),
+        "/:name"           => Ok(()),
+        "/:name.json"      => Ok(()),
+        "/:file.:format"   => Err(conflict("/:file.:format", "/:name.:ext", ":file.:format", ParamName)),
+        "/:from-:to"       => Ok(()),
+        "/:from-*to"       => Err(conflict("/:from-*to", "/:from-:to", ":from-*to", CatchAll)),
+        "/v:major.:minor"  => Ok(()),
+        "/:foo.:bar:baz"   => Err(InsertError::TooManyParams),
+        "/:foo.:bar*baz"   => Err(InsertError::TooManyParams),
+    },
+    escaped_params {
+        "/abc::batchGet"   => Ok(()),
+        "/abc:batchGet"    => Ok(()),
+        "/abc::batchGet"   => Err(conflict("/abc::batchGet", "/abc::batchGet", "abc::batchGet", Duplicate)),
+        "/:id::get"        => Ok(()),
+        "/:name::get"      => Err(conflict("/:name::get", "/:id::get", ":name::get", ParamName)),
+        "/**"              => Ok(()),
+        "/*"               => Err(InsertError::UnnamedParam),
+        "/x::*path"        => Ok(()),
+        "/x:::y"           => Err(conflict("/x:::y", "/x::*path", "x:::y", CatchAll)),
+    },
+    question_marks {
+        "/search?"         => Ok(()),
+        "/search"          => Ok(()),
+        "/help?/:topic?"   => Ok(()),
+        "/x/:id/y?"        => Ok(()),
+        "/x/:id?/z?"       => Err(InsertError::InvalidOptional),
+        "/x/y:id?"         => Err(InsertError::InvalidOptional),
+    },
+}
+
+remove_tests! {
+    remove_normalized {
+        routes = [
+            "/x/:foo/bar",
+            "/x/:bar/baz",
+            "/:foo/:baz/bax",
+            "/:foo/:bar/baz",
+            "/:fod/:baz/:bax/foo",
+            "/:fod/baz/bax/foo",
+            "/:foo/baz/bax",
+            "/:bar/:bay/bay",
+            "/s",
+            "/s/s",
+            "/s/s/s",
+            "/s/s/s/s",
+            "/s/s/:s/x",
+            "/s/s/:y/d",
+        ],
+        "/x/:foo/bar"         => Some("/x/:foo/bar"),
+        "/x/:bar/baz"         => Some("/x/:bar/baz"),
+        "/:foo/:baz/bax"      => Some("/:foo/:baz/bax"),
+        "/:foo/:bar/baz"      => Some("/:foo/:bar/baz"),
+        "/:fod/:baz/:bax/foo" => Some("/:fod/:baz/:bax/foo"),
+        "/:fod/baz/bax/foo"   => Some("/:fod/baz/bax/foo"),
+        "/:foo/baz/bax"       => Some("/:foo/baz/bax"),
+        "/:bar/:bay/bay"      => Some("/:bar/:bay/bay"),
+        "/s"                  => Some("/s"),
+        "/s/s"                => Some("/s/s"),
+        "/s/s/s"              => Some("/s/s/s"),
+        "/s/s/s/s"            => Some("/s/s/s/s"),
+        "/s/s/:s/x"           => Some("/s/s/:s/x"),
+        "/s/s/:y/d"           => Some("/s/s/:y/d"),
+    },
+    remove_test {
+        routes = [
+            "/home",
+            "/home/:id",
+            "/users",
+            "/users/:id",
+            "/users/:id/posts",
+            "/users/:id/posts/:post_id",
+            "/src/*filepath",
+        ],
+        "/home"                     => Some("/home"),
+        "/home"                     => None,
+        "/home/:id"                 => Some("/home/:id"),
+        "/home/:id"                 => None,
+        "/users/:user_id"           => None,
+        "/users"                    => Some("/users"),
+        "/users/:id/posts"          => Some("/users/:id/posts"),
+        "/users/:id/posts/:post_id" => Some("/users/:id/posts/:post_id"),
+        "/src/*file"                => None,
+        "/src/*filepath"            => Some("/src/*filepath"),
+    },
+    remove_merge {
+        routes = [
+            "/home",
+            "/homepage",
+            "/hello",
+            "/help",
+            "/help/:topic",
+            "/:page",
+        ],
+        "/hello"       => Some("/hello"),
+        "/homepage"    => Some("/homepage"),
+        "/home"        => Some("/home"),
+        "/help/:topic" => Some("/help/:topic"),
+        "/:page"       => Some("/:page"),
+    },
+    remove_missing {
+        routes = ["/home", "/home/:id", "/src/*filepath"],
+        "/"           => None,
+        "/ho"         => None,
+        "/homes"      => None,
+        "/home/"      => None,
+        "/home/:id/x" => None,
+        "/home/*id"   => None,
+        "/src/"       => None,
+        "/src/:file"  => None,
+        "/home:id"    => None,
+    },
+    remove_root {
+        routes = ["/", "/:page", "/:page/*rest"],
+        "/"            => Some("/"),
+        "/*rest"       => None,
+        "/:page"       => Some("/:page"),
+        "/:page/*rest" => Some("/:page/*rest"),
+        "/"            => None,
+    },
+    remove_suffixes {
+        routes = [
+            "/:name",
+            "/:name.:ext",
+            "/:name.json",
+            "/:name/raw",
+            "/:from-:to",
+        ],
+        "/:name.:ext" => Some("/:name.:ext"),
+        "/:name.:ext" => None,
+        "/:name.js"   => None,
+        "/:name"      => Some("/:name"),
+        "/:from-:to"  => Some("/:from-:to"),
+        "/:name/raw"  => Some("/:name/raw"),
     },
 }
 
+#[test]
+fn remove_after_conflict() {
+    let mut router = Router::new();
+    for route in [
+        "/users/:id",
+        "/users/:id/posts",
+        "/files/*path",
+        "/home",
+        "/src/a",
+    ] {
+        router.insert(route, route).unwrap();
+    }
+
+    // failed insertions leave the tree untouched, including routes that would split a node
+    assert!(router.insert("/users/:user_id", "x").is_err());
+    assert!(router.insert("/users/:id/posts", "x").is_err());
+    assert!(router.insert("/files/:name", "x").is_err());
+    assert!(router.insert("/src/*path/x", "x").is_err());
+    assert!(router.insert("ab*path", "x").is_err());
+    router.check_priorities().unwrap();
+
+    assert_eq!(router.remove("/users/:id/posts"), Some("/users/:id/posts"));
+    router.check_priorities().unwrap();
+
+    assert!(router.insert("/home", "x").is_err());
+    assert_eq!(router.remove("/home"), Some("/home"));
+    router.check_priorities().unwrap();
+
+    for route in ["/users/:id", "/files/*path", "/src/a"] {
+        assert_eq!(router.remove(route), Some(route));
+        router.check_priorities().unwrap();
+    }
+
+    router.insert("/home", "/home").unwrap();
+    assert_eq!(router.at("/home").map(|m| *m.value), Ok("/home"));
+    router.check_priorities().unwrap();
+}
+
 tsr_tests! {
     tsr {
         routes = [
@@ -660,6 +1536,8 @@ macro_rules! match_tests {
                     .unwrap_or_else(|e| panic!("error when inserting route '{}': {:?}", route, e));
             }
 
+            $(assert_frozen_matches(&router, $path);)*
+
             $(match router.at($path) {
                 Err(_) => {
                     $($( @$some )?
@@ -675,6 +1553,8 @@ macro_rules! match_tests {
                             );
                         }
 
+                        assert_eq!(result.route(), $route, "Wrong route for path '{}'", $path);
+
                         let expected_params = vec![$(($key, $val)),*];
                         let got_params = result.params.iter().collect::<Vec<_>>();
 
@@ -741,6 +1621,8 @@ macro_rules! tsr_tests {
                     .unwrap_or_else(|e| panic!("error when inserting route '{}': {:?}", route, e));
             }
 
+            $(assert_frozen_matches(&router, $path);)*
+
             $(
                 match router.at($path) {
                     Err(MatchError::$tsr) => {},
@@ -752,4 +1634,741 @@ macro_rules! tsr_tests {
    )* };
 }
 
-pub(self) use {insert_tests, match_tests, tsr_tests};
+macro_rules! remove_tests {
+    ($($name:ident {
+        routes = $routes:expr,
+        $($route:literal => $res:expr),* $(,)?
+    }),* $(,)?) => { $(
+        #[test]
+        fn $name() {
+            let mut router = Router::new();
+
+            for route in $routes {
+                router.insert(route, route.to_owned())
+                    .unwrap_or_else(|e| panic!("error when inserting route '{}': {:?}", route, e));
+            }
+
+            let mut removed = Vec::new();
+
+            $(
+                let res = router.remove($route);
+                assert_eq!(res.as_deref(), $res, "unexpected result when removing route '{}'", $route);
+                removed.extend(res);
+
+                if let Err((got, expected)) = router.check_priorities() {
+                    panic!(
+                        "priority mismatch for node after removing '{}': got '{}', expected '{}'",
+                        $route, got, expected
+                    )
+                }
+            )*
+
+            for route in $routes {
+                let matched = router.at(route).ok().map(|m| m.value.as_str());
+
+                if removed.iter().any(|r| r == route) {
+                    assert_ne!(matched, Some(route), "route '{}' was not removed", route);
+                    router.insert(route, route.to_owned())
+                        .unwrap_or_else(|e| panic!("error when reinserting route '{}': {:?}", route, e));
+                } else {
+                    assert_eq!(matched, Some(route), "route '{}' no longer matches", route);
+                }
+            }
+
+            if let Err((got, expected)) = router.check_priorities() {
+                panic!(
+                    "priority mismatch for node: got '{}', expected '{}'",
+                    got, expected
+                )
+            }
+        }
+   )* };
+}
+
+use {insert_tests, match_tests, remove_tests, tsr_tests};
+
+#[test]
+fn parse_params() {
+    let mut router = Router::new();
+    router.insert("/repos/:owner/:id", "repo").unwrap();
+
+    let matched = router.at("/repos/ibraheemdev/42").unwrap();
+    assert_eq!(matched.params.parse::<u32>("id"), Ok(42));
+    assert_eq!(
+        matched.params.parse::<String>("owner").as_deref(),
+        Ok("ibraheemdev")
+    );
+    assert_eq!(
+        matched.params.parse::<u32>("name"),
+        Err(ParamError::Missing { key: "name".into() })
+    );
+
+    let err = matched.params.parse::<u8>("owner").unwrap_err();
+    assert!(matches!(&err, ParamError::Invalid { key, .. } if key == "owner"));
+    assert!(err.to_string().contains("'owner'"));
+}
+
+#[test]
+fn constraints() {
+    let mut router = Router::new();
+    router.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    router.constraint("slug", |s| !s.contains('_'));
+
+    router.insert("/users/:id<num>", "user").unwrap();
+    router.insert("/users/:id<num>/posts", "posts").unwrap();
+    router.insert("/users/new", "new").unwrap();
+    router.insert("/:section/:page<slug>", "page").unwrap();
+    router
+        .insert_named("post", "/posts/:id<num>", "post")
+        .unwrap();
+
+    let matched = router.at("/users/978").unwrap();
+    assert_eq!(*matched.value, "user");
+    assert_eq!(matched.params.get("id"), Some("978"));
+
+    assert_eq!(*router.at("/users/1/posts").unwrap().value, "posts");
+    assert_eq!(*router.at("/users/new").unwrap().value, "new");
+
+    // a rejected value falls through to other routes
+    let matched = router.at("/users/me").unwrap();
+    assert_eq!(*matched.value, "page");
+    assert_eq!(matched.params.get("section"), Some("users"));
+    assert_eq!(matched.params.get("page"), Some("me"));
+
+    assert_eq!(
+        router.at("/users/snake_case").unwrap_err(),
+        MatchError::NotFound
+    );
+    assert_eq!(
+        router.at("/users/me/posts").unwrap_err(),
+        MatchError::NotFound
+    );
+
+    assert_eq!(
+        router.path_ignore_case("/USERS/1/POSTS", false).as_deref(),
+        Some("/users/1/posts")
+    );
+    assert_eq!(router.path_ignore_case("/USERS/x/POSTS", false), None);
+
+    assert_eq!(
+        router.url_for("post", [("id", "1")]).as_deref(),
+        Ok("/posts/1")
+    );
+    assert_eq!(
+        router.url_for("post", [("id", "x")]),
+        Err(UrlError::InvalidParam { name: "id".into() })
+    );
+
+    // the original route is kept
+    assert!(router
+        .iter()
+        .any(|(route, _)| route == "/users/:id<num>/posts"));
+
+    // constraints are part of the route
+    assert_eq!(
+        router.insert("/users/:id", "x"),
+        Err(conflict("/users/:id", "/users/:id<num>", ":id", Constraint))
+    );
+    assert_eq!(
+        router.insert("/users/:id<slug>/x", "x"),
+        Err(conflict(
+            "/users/:id<slug>/x",
+            "/users/:id<num>",
+            ":id<slug>",
+            Constraint
+        ))
+    );
+
+    assert_eq!(router.remove("/users/:id"), None);
+    assert_eq!(router.remove("/users/:id<num>"), Some("user"));
+
+    assert_eq!(
+        router.insert("/a/:id<hex>", "x"),
+        Err(InsertError::UnknownConstraint { name: "hex".into() })
+    );
+    assert_eq!(
+        router.insert("/a/:id<num", "x"),
+        Err(InsertError::InvalidConstraint)
+    );
+    assert_eq!(
+        router.insert("/a/:id<>", "x"),
+        Err(InsertError::InvalidConstraint)
+    );
+    assert_eq!(
+        router.insert("/a/:<num>", "x"),
+        Err(InsertError::UnnamedParam)
+    );
+}
+
+#[test]
+fn optional_segments() {
+    let mut router = Router::new();
+    router.insert("/", "root").unwrap();
+    router.insert("/posts/:page?", "posts").unwrap();
+    router
+        .insert_named("archive", "/archive/:year?/:month?", "archive")
+        .unwrap();
+    router.insert("/blog/:slug", "blog").unwrap();
+
+    assert_eq!(*router.at("/posts").unwrap().value, "posts");
+    let matched = router.at("/posts/2").unwrap();
+    assert_eq!(*matched.value, "posts");
+    assert_eq!(matched.params.get("page"), Some("2"));
+
+    // every form of the route matches the original route
+    assert_eq!(router.at("/posts").unwrap().route(), "/posts/:page?");
+    assert_eq!(router.at("/posts/2").unwrap().route(), "/posts/:page?");
+
+    assert_eq!(*router.at("/archive").unwrap().value, "archive");
+    assert_eq!(*router.at("/archive/2022").unwrap().value, "archive");
+    let matched = router.at("/archive/2022/12").unwrap();
+    assert_eq!(matched.params.get("year"), Some("2022"));
+    assert_eq!(matched.params.get("month"), Some("12"));
+
+    // conflicts are reported against the original route
+    assert_eq!(
+        router.insert("/posts", "x"),
+        Err(conflict("/posts", "/posts/:page?", "posts", Duplicate))
+    );
+
+    // either every form of the route is inserted or none
+    assert_eq!(
+        router.insert("/blog/:page?", "x"),
+        Err(conflict("/blog/:page?", "/blog/:slug", ":page", ParamName))
+    );
+    assert_eq!(router.at("/blog").unwrap_err(), MatchError::NotFound);
+
+    assert_eq!(
+        router.insert("/:a?/b", "x"),
+        Err(InsertError::InvalidOptional)
+    );
+    assert_eq!(
+        router.insert("/files/x:name?", "x"),
+        Err(InsertError::InvalidOptional)
+    );
+
+    assert_eq!(
+        router.url_for("archive", [("year", "2022")]).as_deref(),
+        Ok("/archive/2022")
+    );
+    assert_eq!(
+        router
+            .url_for("archive", [("year", "2022"), ("month", "1")])
+            .as_deref(),
+        Ok("/archive/2022/1")
+    );
+    assert_eq!(
+        router
+            .url_for("archive", Vec::<(&str, &str)>::new())
+            .as_deref(),
+        Ok("/archive")
+    );
+    assert_eq!(
+        router.url_for("archive", [("month", "1")]),
+        Err(UrlError::ExtraParam {
+            name: "month".into()
+        })
+    );
+
+    let mut routes = router.iter().map(|(route, _)| route).collect::<Vec<_>>();
+    routes.sort();
+    assert_eq!(
+        routes,
+        [
+            "/",
+            "/archive/:year?/:month?",
+            "/blog/:slug",
+            "/posts/:page?"
+        ]
+    );
+
+    // every form of the route shares the same value
+    let mut router = Router::new();
+    router.insert("/:lang?", 1).unwrap();
+    router.insert("/posts/:page?", 2).unwrap();
+    *router.at_mut("/").unwrap().value += 10;
+    assert_eq!(*router.at("/en").unwrap().value, 11);
+
+    assert_eq!(router.remove("/"), None);
+    assert_eq!(router.remove("/:lang"), None);
+    assert_eq!(router.remove("/:lang?"), Some(11));
+    assert_eq!(router.at("/").unwrap_err(), MatchError::NotFound);
+    assert_eq!(router.at("/en").unwrap_err(), MatchError::NotFound);
+    assert_eq!(*router.at("/posts").unwrap().value, 2);
+    assert_eq!(*router.at("/posts/1").unwrap().value, 2);
+
+    router.insert("/:lang?", 3).unwrap();
+    assert_eq!(*router.at("/").unwrap().value, 3);
+}
+
+#[test]
+fn optional_catch_all() {
+    let mut router = Router::new();
+    router
+        .insert_named("static", "/static/*path?", "static")
+        .unwrap();
+    router.insert("/*p?", "root").unwrap();
+
+    let matched = router.at("/static/").unwrap();
+    assert_eq!(*matched.value, "static");
+    assert_eq!(matched.params.get("path"), Some(""));
+
+    let matched = router.at("/static/css/main.css").unwrap();
+    assert_eq!(*matched.value, "static");
+    assert_eq!(matched.params.get("path"), Some("css/main.css"));
+
+    let matched = router.at("/").unwrap();
+    assert_eq!(*matched.value, "root");
+    assert_eq!(matched.params.get("p"), Some(""));
+    assert_eq!(router.at("/x").unwrap().params.get("p"), Some("x"));
+
+    // the bare prefix keeps its trailing slash
+    assert_eq!(
+        router.at("/static").unwrap_err(),
+        MatchError::MissingTrailingSlash
+    );
+
+    assert_eq!(
+        router.insert("/static/", "x"),
+        Err(conflict("/static/", "/static/*path?", "static", Duplicate))
+    );
+    assert_eq!(
+        router.insert("/files/:dir?/*path?", "x"),
+        Err(InsertError::InvalidOptional)
+    );
+    assert_eq!(
+        router.insert("/files/*path?/:dir?", "x"),
+        Err(InsertError::InvalidOptional)
+    );
+
+    assert_eq!(
+        router
+            .url_for("static", Vec::<(&str, &str)>::new())
+            .as_deref(),
+        Ok("/static/")
+    );
+    assert_eq!(
+        router.url_for("static", [("path", "a/b")]).as_deref(),
+        Ok("/static/a/b")
+    );
+
+    assert_eq!(router.remove("/static/*path"), None);
+    assert_eq!(router.remove("/static/*path?"), Some("static"));
+    assert_eq!(
+        router.at("/static/").unwrap().params.get("p"),
+        Some("static/")
+    );
+}
+
+#[test]
+fn method_router() {
+    let mut router = MethodRouter::new();
+    router.insert("GET", "/users/:id", "get").unwrap();
+    router.insert("DELETE", "/users/:id", "delete").unwrap();
+    router
+        .insert("GET", "/users/:id/posts/:page?", "posts")
+        .unwrap();
+    router.insert("POST", "/users", "create").unwrap();
+
+    let matched = router.at("GET", "/users/1").unwrap();
+    assert_eq!(*matched.value, "get");
+    assert_eq!(matched.params.get("id"), Some("1"));
+    assert_eq!(matched.route(), "/users/:id");
+    assert_eq!(*router.at("DELETE", "/users/1").unwrap().value, "delete");
+    assert_eq!(*router.at("GET", "/users/1/posts").unwrap().value, "posts");
+
+    // the path exists under other methods
+    assert_eq!(
+        router.at("PUT", "/users/1").unwrap_err(),
+        MethodError::MethodNotAllowed {
+            allowed: vec!["GET".into(), "DELETE".into()]
+        }
+    );
+    assert_eq!(
+        router.at("get", "/users").unwrap_err().allowed(),
+        Some(&["POST".to_owned()][..])
+    );
+
+    // the path does not exist at all
+    assert_eq!(
+        router.at("GET", "/posts").unwrap_err(),
+        MethodError::Match(MatchError::NotFound)
+    );
+    assert_eq!(
+        router.at("GET", "/users/1/").unwrap_err(),
+        MethodError::Match(MatchError::ExtraTrailingSlash)
+    );
+
+    assert!(router.allowed("/users/1").unwrap().eq(["GET", "DELETE"]));
+    assert_eq!(router.allowed("/posts").err(), Some(MatchError::NotFound));
+
+    assert_eq!(
+        router.insert("GET", "/users/:id", "x"),
+        Err(conflict("/users/:id", "/users/:id", ":id", Duplicate))
+    );
+    assert_eq!(
+        router.insert("PUT", "/users/:user_id", "x"),
+        Err(conflict(
+            "/users/:user_id",
+            "/users/:id",
+            ":user_id",
+            ParamName
+        ))
+    );
+
+    *router.at_mut("GET", "/users/1").unwrap().value = "got";
+    assert_eq!(*router.at("GET", "/users/2").unwrap().value, "got");
+
+    assert_eq!(router.remove("PUT", "/users/:id"), None);
+    assert_eq!(router.remove("GET", "/users/:id"), Some("got"));
+    assert!(router.allowed("/users/1").unwrap().eq(["DELETE"]));
+    assert_eq!(router.remove("DELETE", "/users/:id"), Some("delete"));
+    assert_eq!(
+        router.at("DELETE", "/users/1").unwrap_err(),
+        MethodError::Match(MatchError::NotFound)
+    );
+    assert_eq!(
+        *router.at("GET", "/users/1/posts/2").unwrap().value,
+        "posts"
+    );
+}
+
+#[test]
+fn method_router_fallback() {
+    let mut router = MethodRouter::new();
+    router.insert("GET", "/users/me", "me").unwrap();
+    router.insert("POST", "/users/:id", "update").unwrap();
+    router.insert("PUT", "/files/readme", "readme").unwrap();
+    router.insert("GET", "/files/*path", "file").unwrap();
+
+    // a less specific route registered for the method
+    let matched = router.at("POST", "/users/me").unwrap();
+    assert_eq!(*matched.value, "update");
+    assert_eq!(matched.params.get("id"), Some("me"));
+    assert_eq!(matched.route(), "/users/:id");
+    assert_eq!(*router.at("GET", "/users/me").unwrap().value, "me");
+
+    let matched = router.at("GET", "/files/readme").unwrap();
+    assert_eq!(*matched.value, "file");
+    assert_eq!(matched.params.get("path"), Some("readme"));
+
+    *router.at_mut("POST", "/users/me").unwrap().value = "updated";
+    assert_eq!(*router.at("POST", "/users/1").unwrap().value, "updated");
+
+    // the methods of every matching route are allowed
+    assert_eq!(
+        router.at("DELETE", "/users/me").unwrap_err(),
+        MethodError::MethodNotAllowed {
+            allowed: vec!["GET".into(), "POST".into()]
+        }
+    );
+    assert_eq!(
+        router.at_mut("GET", "/users/1").unwrap_err(),
+        MethodError::MethodNotAllowed {
+            allowed: vec!["POST".into()]
+        }
+    );
+    assert!(router.allowed("/users/me").unwrap().eq(["GET", "POST"]));
+    assert!(router.allowed("/files/readme").unwrap().eq(["PUT", "GET"]));
+    assert!(router.allowed("/files/x").unwrap().eq(["GET"]));
+}
+
+#[test]
+fn host_router() {
+    let mut router = HostRouter::new();
+    router.constraint("num", |v| v.bytes().all(|c| c.is_ascii_digit()));
+    router.insert("example.com", "/", "root").unwrap();
+    router
+        .insert("api.example.com", "/users/:id<num>", "user")
+        .unwrap();
+    router
+        .insert(":tenant.example.com", "/dashboard/", "dashboard")
+        .unwrap();
+    router.insert("*sub.example.org", "/:page", "org").unwrap();
+    router
+        .insert("v:major.cdn.example.net", "/*path", "cdn")
+        .unwrap();
+    router.insert("*", "/health", "health").unwrap();
+
+    let matched = router.at("example.com", "/").unwrap();
+    assert_eq!(*matched.value, "root");
+    assert!(matched.params.is_empty());
+
+    // static labels take priority, and the host is matched case-insensitively
+    let matched = router.at("API.Example.com:8080", "/users/1").unwrap();
+    assert_eq!(*matched.value, "user");
+    assert!(matched.params.iter().eq([("id", "1")]));
+
+    let matched = router.at("Acme.example.com.", "/dashboard/").unwrap();
+    assert_eq!(matched.route(), "/dashboard/");
+    assert!(matched.params.iter().eq([("tenant", "Acme")]));
+
+    // a catch-all matches every label to its left
+    let matched = router.at("a.b.example.org", "/about").unwrap();
+    assert!(matched
+        .params
+        .iter()
+        .eq([("sub", "a.b"), ("page", "about")]));
+
+    let matched = router.at("v2.cdn.example.net", "/js/app.js").unwrap();
+    assert!(matched
+        .params
+        .iter()
+        .eq([("major", "2"), ("path", "js/app.js")]));
+
+    // a bare `*` is not reported as a parameter
+    let matched = router.at("[::1]:3000", "/health").unwrap();
+    assert_eq!(*matched.value, "health");
+    assert!(matched.params.is_empty());
+    assert_eq!(*router.at("localhost", "/health").unwrap().value, "health");
+
+    assert_eq!(
+        router.at("example.com", "/users/1").unwrap_err(),
+        MatchError::NotFound
+    );
+    assert_eq!(
+        router.at("acme.example.com", "/dashboard").unwrap_err(),
+        MatchError::MissingTrailingSlash
+    );
+    assert_eq!(
+        router.at("api.example.com", "/users/x").unwrap_err(),
+        MatchError::NotFound
+    );
+    assert_eq!(
+        router.at("example.org", "/about").unwrap_err(),
+        MatchError::NotFound
+    );
+
+    // conflicts are reported between host patterns
+    assert_eq!(
+        router.insert(":name.example.com", "/", "x"),
+        Err(conflict(
+            ":name.example.com",
+            ":tenant.example.com",
+            ":name",
+            ParamName
+        ))
+    );
+    assert_eq!(
+        router.insert(":sub.example.org", "/", "x"),
+        Err(conflict(
+            ":sub.example.org",
+            "*sub.example.org",
+            ":sub",
+            CatchAll
+        ))
+    );
+    assert_eq!(
+        router.insert("api.example.com", "/users/:id<num>", "x"),
+        Err(conflict(
+            "/users/:id<num>",
+            "/users/:id<num>",
+            ":id<num>",
+            Duplicate
+        ))
+    );
+    assert_eq!(
+        router.insert("a*b.example.com", "/", "x"),
+        Err(InsertError::InvalidCatchAll)
+    );
+    assert_eq!(
+        router.insert("**.example.org", "/", "x"),
+        Err(InsertError::InvalidCatchAll)
+    );
+    assert_eq!(
+        router.insert("**sub.example.org", "/", "x"),
+        Err(InsertError::InvalidCatchAll)
+    );
+
+    *router
+        .at_mut("beta.example.com", "/dashboard/")
+        .unwrap()
+        .value = "beta";
+    assert_eq!(
+        *router.at("acme.example.com", "/dashboard/").unwrap().value,
+        "beta"
+    );
+
+    assert_eq!(
+        router.remove(":tenant.example.com", "/dashboard/"),
+        Some("beta")
+    );
+    assert_eq!(router.remove(":tenant.example.com", "/dashboard/"), None);
+    assert_eq!(
+        router.at("acme.example.com", "/dashboard/").unwrap_err(),
+        MatchError::NotFound
+    );
+    router.insert(":name.example.com", "/", "name").unwrap();
+    assert_eq!(
+        router
+            .at("acme.example.com", "/")
+            .unwrap()
+            .params
+            .get("name"),
+        Some("acme")
+    );
+}
+
+#[test]
+fn at_uri() {
+    let mut router = Router::new();
+    router.insert("/users/:id", "user").unwrap();
+    router.insert("/files/*path", "files").unwrap();
+    router.insert("/search/", "search").unwrap();
+
+    // the query is not part of the last parameter
+    let matched = router.at_uri("/users/1?tab=posts&page=2").unwrap();
+    assert_eq!(matched.params.get("id"), Some("1"));
+    assert!(matched.query.iter().eq([("tab", "posts"), ("page", "2")]));
+    assert_eq!(matched.query.as_str(), "tab=posts&page=2");
+
+    let matched = router.at_uri("/files/a/b.txt#L10").unwrap();
+    assert_eq!(matched.params.get("path"), Some("a/b.txt"));
+    assert!(matched.query.is_empty());
+
+    let matched = router.at_uri("/search/?q=a%20b+c#top").unwrap();
+    assert_eq!(*matched.value, "search");
+    assert_eq!(matched.query.get_decoded("q"), Some(Ok("a b c".into())));
+
+    assert_eq!(
+        router.at_uri("/search?q=x").unwrap_err(),
+        MatchError::MissingTrailingSlash
+    );
+    assert_eq!(
+        router.at("/users/1?tab=posts").unwrap().params.get("id"),
+        Some("1?tab=posts")
+    );
+    assert!(router.at("/users/1").unwrap().query.is_empty());
+}
+
+#[test]
+fn frozen_router() {
+    let mut router = Router::new();
+    router.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    router.insert("/users/:id<num>", "user").unwrap();
+    router.insert("/:page/:tab", "tab").unwrap();
+    router.insert("/static/*path?", "static").unwrap();
+    router
+        .insert("/posts/:post_id/comments", "comments")
+        .unwrap();
+    router.insert("/posts/:id", "post").unwrap();
+
+    // redefining a constraint only affects routes inserted afterwards
+    router.constraint("num", |_| false);
+    router.insert("/never/:id<num>", "never").unwrap();
+
+    let frozen = router.freeze().unwrap();
+    let values = router.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+    let value = |path| frozen.at(path).map(|m| values[m.value]);
+
+    assert_eq!(value("/users/978"), Ok("user"));
+    assert_eq!(value("/users/me"), Ok("tab"));
+    assert_eq!(value("/never/1"), Ok("tab"));
+    assert_eq!(value("/posts/1/comments"), Ok("comments"));
+    assert!(frozen.routes().eq(router.iter().map(|(route, _)| route)));
+
+    let matched = frozen.at("/posts/1").unwrap();
+    assert!(matched.params.iter().eq([("id", "1")]));
+
+    let matched = frozen.at("/static/").unwrap();
+    assert_eq!(matched.route(), "/static/*path?");
+    assert!(matched.params.iter().eq([("path", "")]));
+
+    // constraints are not stored in the bytes
+    let mut loaded = FrozenRouter::from_bytes(frozen.as_bytes()).unwrap();
+    assert_eq!(loaded.at("/users/978").unwrap().route(), "/:page/:tab");
+
+    loaded.constraint("num", |id| id.bytes().all(|c| c.is_ascii_digit()));
+    assert_eq!(loaded.at("/users/978").unwrap().route(), "/users/:id<num>");
+    assert_eq!(loaded.at("/never/1").unwrap().route(), "/never/:id<num>");
+
+    let empty = Router::<()>::new().freeze().unwrap();
+    assert_eq!(empty.at("/").unwrap_err(), MatchError::NotFound);
+    assert_eq!(empty.routes().count(), 0);
+
+    let bytes = frozen.into_bytes();
+    assert_eq!(
+        FrozenRouter::from_bytes(&b"not a router"[..]).err(),
+        Some(FrozenError::InvalidHeader)
+    );
+    assert_eq!(
+        FrozenRouter::from_bytes(&bytes[..bytes.len() - 1]).err(),
+        Some(FrozenError::Corrupted)
+    );
+
+    let mut version = bytes.clone();
+    version[8] = 0;
+    assert_eq!(
+        FrozenRouter::from_bytes(version).err(),
+        Some(FrozenError::UnsupportedVersion { version: 0 })
+    );
+
+    // every flipped byte is either rejected or still safe to match against
+    for i in 8..bytes.len() {
+        let mut corrupted = bytes.clone();
+        corrupted[i] ^= 0xFF;
+
+        if let Ok(frozen) = FrozenRouter::from_bytes(corrupted) {
+            for path in ["/users/978", "/static/a/b", "/posts/1/comments", "/", "/x/"] {
+                let _ = frozen.at(path).map(|m| m.params.iter().count());
+            }
+        }
+    }
+}
+
+#[test]
+fn frozen_non_ascii() {
+    let mut router = Router::new();
+    for route in [
+        "/é:id",
+        "/è/:id",
+        "/ü*rest",
+        "/ß:a/€:b",
+        "/日本/:lang",
+        "/日本語",
+    ] {
+        router.insert(route, route.to_owned()).unwrap();
+    }
+
+    let paths = [
+        "/é1",
+        "/è/2",
+        "/üa/b",
+        "/ß1/€2",
+        "/日本/ja",
+        "/日本語",
+        "/日本",
+        "/é",
+        "/ü",
+    ];
+
+    for path in paths {
+        assert_frozen_matches(&router, path);
+    }
+
+    let frozen = router.freeze().unwrap();
+    assert!(frozen.at("/é1").unwrap().params.iter().eq([("id", "1")]));
+    assert!(frozen
+        .at("/üa/b")
+        .unwrap()
+        .params
+        .iter()
+        .eq([("rest", "a/b")]));
+    assert!(frozen
+        .at("/ß1/€2")
+        .unwrap()
+        .params
+        .iter()
+        .eq([("a", "1"), ("b", "2")]));
+
+    // a parameter followed by a non-ASCII suffix
+    let mut router = Router::new();
+    router.constraint("c", |_| true);
+    router.insert("/:id<c>é", 1).unwrap();
+    assert_eq!(router.at("/xé").map(|m| *m.value), Ok(1));
+
+    let frozen = router.freeze().unwrap();
+    let matched = frozen.at("/xé").unwrap();
+    assert_eq!(matched.value, 0);
+    assert!(matched.params.iter().eq([("id", "x")]));
+}